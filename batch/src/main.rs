@@ -11,10 +11,19 @@ use fontdrasil::coords::UserCoord;
 use gf_metadata::GoogleFonts;
 use regex::Regex;
 use skrifa::{MetadataProvider, Tag};
-use stroke_contrast::{WidthReader, csv_fragment, locations_of_interest, normalization_scale};
+use stroke_contrast::{
+    DEFAULT_MIN_STRESS_CONFIDENCE, GlyphSelector, HintedStems, LocationOptions, MeasureOptions,
+    SegmentSelection, ShapingOptions, WidthCurve, csv_fragment, is_default_location,
+    locations_of_interest, measure_glyph_set, measurement_char, normalization_scale,
+    parse_glyph_set,
+};
 
 #[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
+#[command(
+    version,
+    about = "Measure stroke contrast across the Google Fonts collection",
+    long_about = None
+)]
 struct Args {
     /// Where the Google Fonts github repo is cloned
     #[arg(long, default_value = "~/oss/fonts")]
@@ -31,12 +40,52 @@ struct Args {
     /// What file stores values
     #[arg(long, default_value = "~/oss/fonts/tags/all/experimental_quant.csv")]
     target: String,
+
+    /// Only write stress angle when at least this confident in it, 0..=1
+    #[arg(long, default_value_t = DEFAULT_MIN_STRESS_CONFIDENCE)]
+    min_stress_confidence: f64,
+
+    /// Also write 5th and 95th percentile widths, more robust than min and max
+    #[arg(long)]
+    percentiles: bool,
+
+    /// The glyphs to measure, e.g. "o n H v", pooled into a single stroke profile per location.
+    ///
+    /// Glyphs the font doesn't map are skipped, as are glyphs that can't be measured at every
+    /// location, e.g. 'H' by center-of-mass as its center of mass is inked and it has no
+    /// counters. Defaults to a representative glyph for the family's primary script, falling
+    /// back to any script the font supports.
+    #[arg(long)]
+    glyphs: Option<String>,
+
+    /// How to discover strokes
+    #[arg(long, value_enum, default_value_t)]
+    method: SegmentSelection,
+
+    /// Report fonts whose strokes thin as wght increases, usually a measurement failure
+    /// or a broken master
+    #[arg(long)]
+    curve_report: bool,
+
+    /// Report fonts whose CFF stem hints disagree with measured stems, and the TrueType cvt
+    /// values nearest them
+    #[arg(long)]
+    hint_report: bool,
+
+    #[command(flatten)]
+    locations: LocationOptions,
+
+    #[command(flatten)]
+    shaping: ShapingOptions,
+
+    #[command(flatten)]
+    measure: MeasureOptions,
 }
 
 fn flag_path(flag: &str) -> PathBuf {
-    if flag.starts_with("~/") {
+    if let Some(rest) = flag.strip_prefix("~/") {
         let mut d = home_dir().expect("Must have a home dir");
-        d.push(&flag[2..]);
+        d.push(rest);
         d
     } else {
         PathBuf::from(flag)
@@ -46,6 +95,12 @@ fn flag_path(flag: &str) -> PathBuf {
 fn main() {
     const STROKE_WIDTH_MIN_TAG: &str = "/quant/stroke_width_min";
     const STROKE_WIDTH_MAX_TAG: &str = "/quant/stroke_width_max";
+    const STROKE_CONTRAST_TAG: &str = "/quant/stroke_contrast";
+    const STRESS_ANGLE_TAG: &str = "/quant/stress_angle";
+    const STROKE_WIDTH_P5_TAG: &str = "/quant/stroke_width_p5";
+    const STROKE_WIDTH_P95_TAG: &str = "/quant/stroke_width_p95";
+    const VERTICAL_STEM_WIDTH_TAG: &str = "/quant/vertical_stem_width";
+    const HORIZONTAL_BAR_WIDTH_TAG: &str = "/quant/horizontal_bar_width";
     const WGHT_TAG: Tag = Tag::new(b"wght");
     const ITAL_TAG: Tag = Tag::new(b"ital");
 
@@ -63,6 +118,14 @@ fn main() {
     let gf = GoogleFonts::new(gf_repo, family_filter);
 
     println!("Writing tags to {target_file:?}");
+    let glyphs = args.glyphs.as_deref().map(parse_glyph_set);
+    println!(
+        "Measuring {} by {:?} shaped with {:?} with {:?}",
+        args.glyphs.as_deref().unwrap_or("per script glyphs"),
+        args.method,
+        args.shaping,
+        args.measure
+    );
     let existing_tags = gf
         .tags()
         .expect("To read tags")
@@ -79,11 +142,11 @@ fn main() {
     let mut families = gf
         .families()
         .iter()
-        .filter_map(|(p, f)| f.as_ref().ok().and_then(|f| Some((p, f))))
+        .filter_map(|(p, f)| f.as_ref().ok().map(|f| (p, f)))
         .collect::<Vec<_>>();
     families.sort_by_key(|(_, f)| f.name());
 
-    for (local_path, family, font) in families
+    'fonts: for (local_path, family, font) in families
         .iter()
         .filter(|(_, f)| family_names.contains(f.name()))
         .flat_map(|(p, f)| f.fonts.iter().map(move |font| (p, f, font)))
@@ -108,12 +171,30 @@ fn main() {
             fs::read(&font_path).unwrap_or_else(|e| panic!("Unable to read {font_path:?}: {e}"));
         let font_ref = skrifa::FontRef::new(&raw_font).expect("A font");
 
-        if font_ref.charmap().map('o').is_none() {
-            eprintln!("Measurement char not supported by {}", font.filename());
+        let to_measure = match &glyphs {
+            Some(glyphs) => {
+                let charmap = font_ref.charmap();
+                glyphs
+                    .iter()
+                    .copied()
+                    .filter(|ch| charmap.map(*ch).is_some())
+                    .map(GlyphSelector::from)
+                    .collect::<Vec<_>>()
+            }
+            None => {
+                let primary_script = family.has_primary_script().then(|| family.primary_script());
+                measurement_char(&font_ref, primary_script)
+                    .into_iter()
+                    .map(GlyphSelector::from)
+                    .collect()
+            }
+        };
+        if to_measure.is_empty() {
+            eprintln!("No measurement char supported by {}", font.filename());
             continue;
         }
 
-        let mut user_locs = locations_of_interest(&font_ref);
+        let mut user_locs = locations_of_interest(&font_ref, &args.locations);
         let scale = normalization_scale(&font_ref);
         let italic = match font.style() {
             "italic" => true,
@@ -130,17 +211,84 @@ fn main() {
             }
         }
 
-        let mut tag_lines = Vec::new();
-        for user_loc in user_locs {
-            let norm_loc = font_ref.axes().location(
-                &user_loc
-                    .iter()
-                    .map(|(tag, coord)| (tag.clone(), coord.to_f64() as f32))
-                    .collect::<Vec<_>>(),
+        let measured = user_locs
+            .iter()
+            .map(|user_loc| {
+                let norm_loc = font_ref.axes().location(
+                    user_loc
+                        .iter()
+                        .map(|(tag, coord)| (*tag, coord.to_f64() as f32))
+                        .collect::<Vec<_>>(),
+                );
+                measure_glyph_set(
+                    &raw_font,
+                    &to_measure,
+                    &args.shaping,
+                    &norm_loc,
+                    args.method,
+                    args.measure,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        // Profile the same glyphs everywhere, those that fail anywhere are left out
+        for (user_loc, measured) in user_locs.iter().zip(measured.iter()) {
+            for (glyph, measurement) in measured.glyphs.iter() {
+                if let Err(e) = measurement {
+                    eprintln!(
+                        "Unable to measure {glyph} in {} at {}, leaving it out: {e}",
+                        font.filename(),
+                        csv_fragment(user_loc)
+                    );
+                }
+            }
+        }
+        let pooled = to_measure
+            .iter()
+            .filter(|glyph| measured.iter().all(|m| m.measured(glyph)))
+            .cloned()
+            .collect::<Vec<_>>();
+        if pooled.is_empty() {
+            eprintln!(
+                "No glyph could be measured at every location of {}",
+                font.filename()
             );
-            let builder = WidthReader::new(&raw_font, 'o', &norm_loc);
+            continue;
+        }
 
-            let width_candidates = builder.cast_rays_around_center_of_mass();
+        let mut tag_lines = Vec::new();
+        let mut measurements = Vec::new();
+        for (user_loc, measured) in user_locs.into_iter().zip(measured) {
+            // Don't write partial results for a font
+            let Some(width_candidates) = measured.profile_of(&pooled) else {
+                continue 'fonts;
+            };
+            if args.hint_report && is_default_location(&font_ref, &user_loc) {
+                let hints = HintedStems::new(&font_ref);
+                for disagreement in hints.compare(&width_candidates.stems) {
+                    println!(
+                        "{} {} {:.2} disagrees with measured {:.2}",
+                        font.filename(),
+                        disagreement.source,
+                        disagreement.hinted * scale,
+                        disagreement.measured * scale
+                    );
+                }
+                for nearest in hints.nearest_cvt(&width_candidates.stems) {
+                    println!(
+                        "{} cvt {:.2} is nearest measured {} {:.2}",
+                        font.filename(),
+                        nearest.cvt * scale,
+                        nearest.stem,
+                        nearest.measured * scale
+                    );
+                }
+            }
+            measurements.push((
+                user_loc.clone(),
+                width_candidates.min_width * scale,
+                width_candidates.max_width * scale,
+            ));
             // Emit tags in normalized scale
 
             tag_lines.push(format!(
@@ -155,6 +303,61 @@ fn main() {
                 csv_fragment(&user_loc),
                 width_candidates.max_width * scale
             ));
+            tag_lines.push(format!(
+                "{},{},{STROKE_CONTRAST_TAG},{:.2}",
+                family.name(),
+                csv_fragment(&user_loc),
+                width_candidates.contrast.ratio
+            ));
+            if let Some(stress) = width_candidates.stress()
+                && stress.confidence >= args.min_stress_confidence
+            {
+                tag_lines.push(format!(
+                    "{},{},{STRESS_ANGLE_TAG},{:.1}",
+                    family.name(),
+                    csv_fragment(&user_loc),
+                    stress.angle
+                ));
+            }
+            if args.percentiles {
+                tag_lines.push(format!(
+                    "{},{},{STROKE_WIDTH_P5_TAG},{:.2}",
+                    family.name(),
+                    csv_fragment(&user_loc),
+                    width_candidates.distribution.p5 * scale
+                ));
+                tag_lines.push(format!(
+                    "{},{},{STROKE_WIDTH_P95_TAG},{:.2}",
+                    family.name(),
+                    csv_fragment(&user_loc),
+                    width_candidates.distribution.p95 * scale
+                ));
+            }
+            if let Some(vertical_stem) = &width_candidates.stems.vertical_stem {
+                tag_lines.push(format!(
+                    "{},{},{VERTICAL_STEM_WIDTH_TAG},{:.2}",
+                    family.name(),
+                    csv_fragment(&user_loc),
+                    vertical_stem.median * scale
+                ));
+            }
+            if let Some(horizontal_bar) = &width_candidates.stems.horizontal_bar {
+                tag_lines.push(format!(
+                    "{},{},{HORIZONTAL_BAR_WIDTH_TAG},{:.2}",
+                    family.name(),
+                    csv_fragment(&user_loc),
+                    horizontal_bar.median * scale
+                ));
+            }
+        }
+
+        if args.curve_report {
+            for curve in WidthCurve::along(WGHT_TAG, &measurements)
+                .iter()
+                .filter(|c| !c.is_monotonic())
+            {
+                println!("{} {curve}", font.filename());
+            }
         }
 
         let mut file = OpenOptions::new()
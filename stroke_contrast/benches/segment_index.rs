@@ -0,0 +1,98 @@
+//! Compare brute force path queries with [SegmentIndex] on glyph-like paths with many segments.
+//!
+//! Run with `cargo bench --bench segment_index`. That the two agree is checked by the tests in
+//! index.rs.
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use kurbo::{Affine, Line, ParamCurve, ParamCurveNearest, Point, Shape};
+use stroke_contrast::SegmentIndex;
+use test_util::{query_points, wobbly_o};
+
+// The same paths the index is tested on, loc and the axis tags go unused here
+#[allow(dead_code)]
+#[path = "../src/test_util.rs"]
+mod test_util;
+
+fn rays(points: &[Point]) -> Vec<Line> {
+    points
+        .iter()
+        .enumerate()
+        .map(|(i, pt)| {
+            Affine::rotate_about(i as f64, *pt)
+                * Line::new(*pt - (2000.0, 0.0), *pt + (2000.0, 0.0))
+        })
+        .collect()
+}
+
+fn time(f: impl FnOnce()) -> Duration {
+    let start = Instant::now();
+    f();
+    start.elapsed()
+}
+
+fn report(query: &str, segments: usize, brute: Duration, indexed: Duration) {
+    println!(
+        "{query:>10} {segments:>6} segments: brute force {:>9.2}ms, indexed {:>7.2}ms, {:>6.1}x",
+        brute.as_secs_f64() * 1000.0,
+        indexed.as_secs_f64() * 1000.0,
+        brute.as_secs_f64() / indexed.as_secs_f64()
+    );
+}
+
+fn main() {
+    let points = query_points(1000);
+    let rays = rays(&points);
+    for segments_per_contour in [50, 500, 2500] {
+        let path = wobbly_o(segments_per_contour);
+        let segments = path.segments().count();
+        let index = SegmentIndex::new(&path);
+        let brute = time(|| {
+            for pt in points.iter() {
+                black_box(path.winding(*pt));
+            }
+        });
+        let indexed = time(|| {
+            for pt in points.iter() {
+                black_box(index.winding(*pt));
+            }
+        });
+        report("winding", segments, brute, indexed);
+
+        let brute = time(|| {
+            for ray in rays.iter() {
+                black_box(
+                    path.segments()
+                        .flat_map(|s| s.intersect_line(*ray).into_iter().map(move |i| (i, s)))
+                        .collect::<Vec<_>>(),
+                );
+            }
+        });
+        let indexed = time(|| {
+            for ray in rays.iter() {
+                black_box(index.intersect_line(*ray));
+            }
+        });
+        report("intersect", segments, brute, indexed);
+
+        let brute = time(|| {
+            for pt in points.iter() {
+                black_box(
+                    path.segments()
+                        .map(|s| (s, s.nearest(*pt, 0.000001)))
+                        .min_by(|a, b| a.1.distance_sq.total_cmp(&b.1.distance_sq))
+                        .map(|(s, nearest)| s.eval(nearest.t)),
+                );
+            }
+        });
+        let indexed = time(|| {
+            for pt in points.iter() {
+                black_box(index.nearest(*pt, 0.000001));
+            }
+        });
+        report("nearest", segments, brute, indexed);
+    }
+}
@@ -1,24 +1,39 @@
 use clap::Parser;
-
-#[derive(Debug, Default, Copy, Clone, clap::ValueEnum)]
-pub(crate) enum SegmentSelection {
-    /// Cast rays from center of mass, stopping at nearest path segment
-    #[default]
-    CenterOfMass,
-    /// Cast multiple rays perpendicular to each path segment
-    AllSegments,
-}
+use fontdrasil::coords::{UserCoord, UserLocation};
+use skrifa::Tag;
+use stroke_contrast::{
+    DEFAULT_MIN_STRESS_CONFIDENCE, LocationOptions, MeasureOptions, SegmentSelection,
+    ShapingOptions,
+};
 
 #[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
+#[command(version, about = "Measure the stroke widths of glyphs in a font", long_about = None)]
 pub(crate) struct Args {
     /// Where to save svg files
     #[arg(short, long, default_value = "/tmp/an.svg")]
     pub(crate) output_svg: String,
 
-    /// The text to draw. You probably want to just leave it as o.
-    #[arg(short, long, default_value_t = 'o')]
-    pub(crate) char: char,
+    /// The glyphs to measure, e.g. "o n H v".
+    ///
+    /// Each is measured separately and the results pooled into a single stroke profile.
+    /// Defaults to a representative glyph the font supports, preferring --script, o for
+    /// Latin fonts, unless --text, --glyph or --gid is given. --char still works, as it did when
+    /// only one glyph could be measured.
+    #[arg(short, long, alias = "char")]
+    pub(crate) chars: Option<String>,
+
+    /// Shape text, e.g. a word or a base with marks, and measure it as a single outline.
+    /// May be repeated.
+    #[arg(long)]
+    pub(crate) text: Vec<String>,
+
+    /// Measure a glyph by post or CFF name, e.g. a.sc, bypassing shaping. May be repeated.
+    #[arg(long)]
+    pub(crate) glyph: Vec<String>,
+
+    /// Measure a glyph by id, bypassing shaping. May be repeated.
+    #[arg(long)]
+    pub(crate) gid: Vec<u32>,
 
     /// The font to process
     #[arg(long)]
@@ -36,9 +51,57 @@ pub(crate) struct Args {
     #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
     pub(crate) show_rays: bool,
 
+    /// Only emit stress angle when at least this confident in it, 0..=1
+    #[arg(long, default_value_t = DEFAULT_MIN_STRESS_CONFIDENCE)]
+    pub(crate) min_stress_confidence: f64,
+
+    /// Also emit 5th and 95th percentile widths, more robust than min and max
+    #[arg(long)]
+    pub(crate) percentiles: bool,
+
+    /// Compare measured stems at the default location with CFF stem hints, and report the
+    /// TrueType cvt values nearest them
+    #[arg(long)]
+    pub(crate) hints: bool,
+
+    /// Report stroke width versus wght and flag strokes that thin as weight increases
+    #[arg(long)]
+    pub(crate) curve: bool,
+
+    /// Estimate widths at a user location, e.g. wght=537,wdth=85, by interpolating between
+    /// the locations measured. May be repeated.
+    #[arg(long, value_parser = parse_user_location)]
+    pub(crate) estimate: Vec<UserLocation>,
+
+    #[command(flatten)]
+    pub(crate) locations: LocationOptions,
+
+    #[command(flatten)]
+    pub(crate) shaping: ShapingOptions,
+
+    #[command(flatten)]
+    pub(crate) measure: MeasureOptions,
+
     /// Set the log level, either globally or per module.
     ///
     /// See <https://docs.rs/env_logger/latest/env_logger/#enabling-logging> for format.
     #[arg(long)]
     pub(crate) log: Option<String>,
 }
+
+/// Parse tag=value pairs separated by commas, e.g. wght=537,wdth=85
+fn parse_user_location(s: &str) -> Result<UserLocation, String> {
+    let mut loc = UserLocation::new();
+    for setting in s.split(',').filter(|s| !s.trim().is_empty()) {
+        let Some((tag, value)) = setting.split_once('=') else {
+            return Err(format!("{setting:?} is not tag=value"));
+        };
+        let tag = Tag::new_checked(tag.trim().as_bytes()).map_err(|e| format!("{tag:?}: {e}"))?;
+        let value = value
+            .trim()
+            .parse::<f64>()
+            .map_err(|e| format!("{value:?}: {e}"))?;
+        loc.insert(tag, UserCoord::new(value));
+    }
+    Ok(loc)
+}
@@ -0,0 +1,218 @@
+use std::{
+    cell::Cell,
+    fmt::{self, Display},
+    time::{Duration, Instant},
+};
+
+use crate::MeasureOptions;
+
+/// Once a budget is exhausted circles are fit to at most this many of the ribs found, for a partial
+/// result. That may take a further [PARTIAL_BUDGET] of the budget.
+pub(crate) const MAX_PARTIAL_RIBS: usize = 1000;
+
+/// The fraction of an exhausted budget a partial result may take
+const PARTIAL_BUDGET: f64 = 0.1;
+
+/// A limit on how much work a measurement may do, see [MeasureOptions]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Budget {
+    /// The measurement would cast more than this many rays
+    Rays(usize),
+    /// More than this many segment intersection, winding or nearest point tests
+    IntersectionTests(usize),
+    /// Measurement took longer than this
+    Timeout(Duration),
+}
+
+impl Display for Budget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Budget::Rays(max) => write!(f, "more than {max} rays"),
+            Budget::IntersectionTests(max) => write!(f, "more than {max} intersection tests"),
+            Budget::Timeout(max) => write!(f, "more than {:.2}s", max.as_secs_f64()),
+        }
+    }
+}
+
+/// Work done before measuring, e.g. preparing the outline, that every measurement is charged for
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Spent {
+    elapsed: Duration,
+    tests: usize,
+}
+
+/// Tracks the work done by a single measurement against the budgets in [MeasureOptions]
+///
+/// Every query against the whole path is charged one test per segment, as if it were brute force,
+/// so budgets don't depend on how effective the [crate::SegmentIndex] is for a given glyph.
+pub(crate) struct Work {
+    started: Instant,
+    segment_count: usize,
+    tests: Cell<usize>,
+    max_tests: Option<usize>,
+    timeout: Option<Duration>,
+}
+
+impl Work {
+    pub(crate) fn new(options: &MeasureOptions, segment_count: usize) -> Self {
+        Self::after(Spent::default(), options, segment_count)
+    }
+
+    /// Start tracking with what was already spent charged
+    pub(crate) fn after(spent: Spent, options: &MeasureOptions, segment_count: usize) -> Self {
+        let now = Instant::now();
+        Self {
+            started: now.checked_sub(spent.elapsed).unwrap_or(now),
+            segment_count,
+            tests: Cell::new(spent.tests),
+            max_tests: options.max_intersection_tests,
+            timeout: options.timeout,
+        }
+    }
+
+    /// A fresh budget, a fraction of this one, to produce a partial result in once this is exhausted
+    pub(crate) fn partial(&self) -> Self {
+        Self {
+            started: Instant::now(),
+            segment_count: self.segment_count,
+            tests: Cell::new(0),
+            max_tests: self
+                .max_tests
+                .map(|max| (PARTIAL_BUDGET * max as f64).ceil() as usize),
+            timeout: self.timeout.map(|timeout| timeout.mul_f64(PARTIAL_BUDGET)),
+        }
+    }
+
+    pub(crate) fn spent(&self) -> Spent {
+        Spent {
+            elapsed: self.started.elapsed(),
+            tests: self.tests.get(),
+        }
+    }
+
+    /// Record a query that tests every segment of the path
+    pub(crate) fn charge_path_query(&self) {
+        self.charge_query(self.segment_count);
+    }
+
+    /// Record a query that tests this many segments
+    pub(crate) fn charge_query(&self, segments: usize) {
+        self.tests.set(self.tests.get() + segments);
+    }
+
+    /// Fails if any budget is exhausted
+    pub(crate) fn check(&self) -> Result<(), Budget> {
+        if let Some(max_tests) = self.max_tests
+            && self.tests.get() > max_tests
+        {
+            return Err(Budget::IntersectionTests(max_tests));
+        }
+        if let Some(timeout) = self.timeout
+            && self.started.elapsed() > timeout
+        {
+            return Err(Budget::Timeout(timeout));
+        }
+        Ok(())
+    }
+}
+
+/// Fails if a measurement would cast more rays than allowed
+pub(crate) fn check_ray_count(options: &MeasureOptions, rays: usize) -> Result<(), Budget> {
+    match options.max_rays {
+        Some(max_rays) if rays > max_rays => Err(Budget::Rays(max_rays)),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{thread, time::Duration};
+
+    use crate::MeasureOptions;
+
+    use super::{Budget, Spent, Work, check_ray_count};
+
+    #[test]
+    fn ray_budget() {
+        let options = MeasureOptions {
+            max_rays: Some(360),
+            ..Default::default()
+        };
+        assert_eq!(Ok(()), check_ray_count(&options, 360));
+        assert_eq!(Err(Budget::Rays(360)), check_ray_count(&options, 361));
+        assert_eq!(Ok(()), check_ray_count(&Default::default(), usize::MAX));
+    }
+
+    #[test]
+    fn intersection_test_budget() {
+        let options = MeasureOptions {
+            max_intersection_tests: Some(25),
+            ..Default::default()
+        };
+        // Every path query is charged as a test of all 10 segments
+        let work = Work::new(&options, 10);
+        work.charge_path_query();
+        work.charge_path_query();
+        assert_eq!(Ok(()), work.check());
+        work.charge_path_query();
+        assert_eq!(Err(Budget::IntersectionTests(25)), work.check());
+
+        let unlimited = Work::new(&Default::default(), 10);
+        for _ in 0..1000 {
+            unlimited.charge_path_query();
+        }
+        assert_eq!(Ok(()), unlimited.check());
+    }
+
+    #[test]
+    fn timeout_budget() {
+        let timeout = Duration::from_millis(10);
+        let options = MeasureOptions {
+            timeout: Some(timeout),
+            ..Default::default()
+        };
+        let work = Work::new(&options, 10);
+        assert_eq!(Ok(()), work.check());
+        thread::sleep(2 * timeout);
+        assert_eq!(Err(Budget::Timeout(timeout)), work.check());
+    }
+
+    #[test]
+    fn work_after_spent() {
+        let options = MeasureOptions {
+            max_intersection_tests: Some(25),
+            ..Default::default()
+        };
+        let before = Work::new(&options, 10);
+        before.charge_path_query();
+        before.charge_query(6);
+        let work = Work::after(before.spent(), &options, 10);
+        assert_eq!(Ok(()), work.check());
+        work.charge_path_query();
+        assert_eq!(Err(Budget::IntersectionTests(25)), work.check());
+        assert_eq!(Ok(()), Work::after(Spent::default(), &options, 10).check());
+    }
+
+    #[test]
+    fn partial_budget_is_a_fraction() {
+        let options = MeasureOptions {
+            max_intersection_tests: Some(1000),
+            ..Default::default()
+        };
+        let work = Work::new(&options, 10);
+        for _ in 0..101 {
+            work.charge_path_query();
+        }
+        assert!(work.check().is_err());
+
+        // Starts afresh with a tenth of the budget
+        let partial = work.partial();
+        assert_eq!(Ok(()), partial.check());
+        for _ in 0..10 {
+            partial.charge_path_query();
+        }
+        assert_eq!(Ok(()), partial.check());
+        partial.charge_path_query();
+        assert_eq!(Err(Budget::IntersectionTests(100)), partial.check());
+    }
+}
@@ -0,0 +1,704 @@
+//! Make sense of contours before measuring them.
+//!
+//! Measurement asks whether points are inked and where rays cross the outline. Counters drawn in
+//! the wrong direction and contours that overlap give answers that don't match what the designer
+//! drew, so such outlines are redrawn as the boundary of the ink they render under the fill rule:
+//! overlaps are merged and every contour runs the same way around its ink. What is inked doesn't
+//! change. A glyph drawn as a hole in a box is inverted by dropping the box.
+
+use std::fmt::{self, Display};
+
+use kurbo::{BezPath, Line, ParamCurve, ParamCurveNearest, PathEl, PathSeg, Point, Shape, Vec2};
+use log::warn;
+
+use crate::{Budget, SegmentIndex, Tangent, budget::Work, is_degenerate};
+
+/// A reverse video frame inks at least this fraction of its own bounding box, allowing for
+/// rounded corners. A heavy 'o' or 'B' falls well short.
+const MIN_FRAME_FILL: f64 = 0.95;
+
+/// Points along each segment probed to decide how contours relate
+const CONTOUR_PROBES: usize = 8;
+
+/// Points along each segment probed for ink on either side
+const BOUNDARY_SAMPLES: usize = 16;
+
+/// Halvings to find where a segment enters or leaves ink, to 1/2^20 of a sample apart
+const BOUNDARY_BISECTIONS: usize = 20;
+
+/// How far, relative to the size of the outline, to probe either side of it. Small enough that
+/// probes on abutting contours don't land in each other.
+const PROBE_OFFSET: f64 = 1e-6;
+
+/// How close, relative to the size of the outline, pieces of the ink boundary must end to join
+const JOIN_TOLERANCE: f64 = 1e-3;
+
+/// How winding numbers map to ink
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+pub enum FillRule {
+    /// Inked where the winding number is not zero, as TrueType and CFF render
+    #[default]
+    NonZero,
+    /// Inked where the winding number is odd, contour direction is irrelevant
+    EvenOdd,
+}
+
+impl FillRule {
+    pub fn is_inked(&self, winding: i32) -> bool {
+        match self {
+            FillRule::NonZero => winding != 0,
+            FillRule::EvenOdd => winding % 2 != 0,
+        }
+    }
+
+    /// The value of the svg fill-rule attribute
+    pub fn svg_name(&self) -> &'static str {
+        match self {
+            FillRule::NonZero => "nonzero",
+            FillRule::EvenOdd => "evenodd",
+        }
+    }
+}
+
+/// What we found, and corrected, in the contours of an outline
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ContourReport {
+    pub contours: usize,
+    /// Under nonzero, contours whose direction didn't alternate with nesting depth, e.g. a
+    /// counter drawn the same way as the outline around it. Such a counter renders inked so is
+    /// merged into the ink around it, anything else is reversed.
+    pub misoriented: usize,
+    /// Contours whose inside overlaps that of another. These were merged.
+    pub overlapping: usize,
+    /// Misoriented or overlapping contours couldn't be merged, they are measured as drawn with
+    /// segments that run through ink ignored
+    pub unmerged: bool,
+    /// The glyph was drawn as a hole in a box, the box was dropped so the hole is measured
+    pub inverted: bool,
+}
+
+impl ContourReport {
+    /// True if nothing needed correcting
+    pub fn is_clean(&self) -> bool {
+        self.misoriented == 0 && self.overlapping == 0 && !self.inverted
+    }
+}
+
+impl Display for ContourReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} contours, {} misoriented, {} overlapping",
+            self.contours, self.misoriented, self.overlapping
+        )?;
+        if self.unmerged {
+            f.write_str(", unmerged")?;
+        }
+        if self.inverted {
+            f.write_str(", reverse video inverted")?;
+        }
+        Ok(())
+    }
+}
+
+struct Contour {
+    path: BezPath,
+    /// Signed area, the sign gives direction
+    area: f64,
+    /// Points just inside every segment, used to decide how contours relate
+    probes: Vec<Point>,
+    /// How many other contours contain this one
+    depth: usize,
+    /// Winding number just inside this contour, counting it and the contours containing it
+    winding: i32,
+}
+
+/// An outline ready to measure
+pub(crate) struct Normalized {
+    pub(crate) path: BezPath,
+    /// Contours that enclose a counter, i.e. those at odd nesting depth with no ink just inside
+    pub(crate) counters: Vec<BezPath>,
+    pub(crate) report: ContourReport,
+}
+
+/// Split path into contours, redraw misoriented and overlapping ones as the boundary of their ink,
+/// drop a reverse video frame and find the counters.
+///
+/// The frame is only dropped if more than reverse_video_area is inked and a single box shaped
+/// contour contains all the others, each of them a hole in it. Winding queries are charged to
+/// work, failing if it runs out.
+pub(crate) fn normalize(
+    path: &BezPath,
+    fill_rule: FillRule,
+    reverse_video_area: f64,
+    work: &Work,
+) -> Result<Normalized, Budget> {
+    let bbox = path.bounding_box();
+    let size = bbox.width().max(bbox.height());
+    let mut contours = split(path, size);
+    let (mut contains, overlaps) = relate(&mut contours, work)?;
+    let mut report = ContourReport {
+        contours: contours.len(),
+        overlapping: overlaps.iter().filter(|o| **o).count(),
+        ..Default::default()
+    };
+
+    // Fonts disagree on which way outer contours go so take the biggest outermost one as right
+    let outer_direction = contours
+        .iter()
+        .filter(|c| c.depth == 0)
+        .max_by(|a, b| a.area.abs().total_cmp(&b.area.abs()))
+        .map(|c| c.area.signum())
+        .unwrap_or(1.0);
+    if fill_rule == FillRule::NonZero {
+        report.misoriented = contours
+            .iter()
+            .filter(|c| {
+                let expected = if c.depth % 2 == 0 {
+                    outer_direction
+                } else {
+                    -outer_direction
+                };
+                c.area != 0.0 && c.area.signum() != expected
+            })
+            .count();
+    }
+
+    if report.misoriented > 0 || report.overlapping > 0 {
+        match merge(path, fill_rule, size, work)? {
+            Some(merged) => {
+                warn!(
+                    "{} misoriented and {} overlapping contours, measuring the outline of their ink",
+                    report.misoriented, report.overlapping
+                );
+                contours = split(&merged, size);
+                (contains, _) = relate(&mut contours, work)?;
+            }
+            None => {
+                warn!(
+                    "Unable to merge {} misoriented and {} overlapping contours, measuring as drawn",
+                    report.misoriented, report.overlapping
+                );
+                report.unmerged = true;
+            }
+        }
+    }
+
+    if inked_area(&contours, &contains, fill_rule) > reverse_video_area {
+        // A frame is a box around holes, every other contour, dropping it turns the holes into
+        // ink. Heavy letters with counters also have a contour containing the rest, but it isn't
+        // a box, and a box around ink is just a block.
+        match (0..contours.len()).find(|i| {
+            contours.len() > 1
+                && (0..contours.len()).all(|j| *i == j || contains[*i][j])
+                && contours
+                    .iter()
+                    .filter(|c| c.depth == contours[*i].depth + 1)
+                    .all(|c| !fill_rule.is_inked(c.winding))
+                && contours[*i].area.abs()
+                    >= MIN_FRAME_FILL * contours[*i].path.bounding_box().area()
+        }) {
+            Some(frame) => {
+                warn!("Reverse video, measuring the glyph cut out of the surrounding box");
+                let frame = contours.remove(frame);
+                // Everything else was inside the frame
+                for contour in contours.iter_mut() {
+                    contour.depth -= 1;
+                    contour.winding -= direction(frame.area);
+                }
+                report.inverted = true;
+            }
+            None => warn!("Reverse video? Most of the glyph box is inked but no box frames it"),
+        }
+    }
+
+    let mut normalized = BezPath::new();
+    let mut counters = Vec::new();
+    for contour in contours {
+        normalized.extend(contour.path.iter());
+        if contour.depth % 2 == 1 && !fill_rule.is_inked(contour.winding) {
+            counters.push(contour.path);
+        }
+    }
+    Ok(Normalized {
+        path: normalized,
+        counters,
+        report,
+    })
+}
+
+/// Work out which contours contain which, setting their depth and winding, and which overlap.
+///
+/// Returns contains, contains[i][j] if contour i contains contour j, and whether each contour
+/// overlaps another. Winding is asked of an index as it copes with probes level with a vertex.
+/// A probe just inside one contour can still land either side of another that touches it so
+/// containment goes by majority, while probes on both sides mean the two overlap.
+fn relate(contours: &mut [Contour], work: &Work) -> Result<(Vec<Vec<bool>>, Vec<bool>), Budget> {
+    let indices = contours
+        .iter()
+        .map(|c| SegmentIndex::new(&c.path))
+        .collect::<Vec<_>>();
+    let mut contains = vec![vec![false; contours.len()]; contours.len()];
+    let mut overlaps = vec![false; contours.len()];
+    for i in 0..contours.len() {
+        for j in 0..contours.len() {
+            if i == j || contours[j].probes.is_empty() {
+                continue;
+            }
+            work.check()?;
+            let inside = contours[j]
+                .probes
+                .iter()
+                .filter(|pt| {
+                    work.charge_query(indices[i].len());
+                    indices[i].winding(**pt) != 0
+                })
+                .count();
+            if 2 * inside > contours[j].probes.len() {
+                contains[i][j] = true;
+            }
+            if inside > 0 && inside < contours[j].probes.len() {
+                overlaps[i] = true;
+                overlaps[j] = true;
+            }
+        }
+    }
+    let directions = contours
+        .iter()
+        .map(|c| direction(c.area))
+        .collect::<Vec<_>>();
+    for (j, contour) in contours.iter_mut().enumerate() {
+        let containers = (0..contains.len()).filter(|i| contains[*i][j]);
+        contour.depth = containers.clone().count();
+        contour.winding = directions[j] + containers.map(|i| directions[i]).sum::<i32>();
+    }
+    Ok((contains, overlaps))
+}
+
+/// Area inked under fill_rule, assuming contours don't overlap.
+///
+/// Each contour bounds the region inside it but outside the contours directly inside it, that
+/// region is inked if the winding just inside the contour is.
+fn inked_area(contours: &[Contour], contains: &[Vec<bool>], fill_rule: FillRule) -> f64 {
+    (0..contours.len())
+        .filter(|i| fill_rule.is_inked(contours[*i].winding))
+        .map(|i| {
+            let children = (0..contours.len())
+                .filter(|j| contains[i][*j] && contours[*j].depth == contours[i].depth + 1)
+                .map(|j| contours[j].area.abs())
+                .sum::<f64>();
+            contours[i].area.abs() - children
+        })
+        .sum()
+}
+
+/// The outline of what path inks under fill_rule, None if the pieces don't join up.
+///
+/// Outer contours have positive area, counters negative.
+fn merge(
+    path: &BezPath,
+    fill_rule: FillRule,
+    size: f64,
+    work: &Work,
+) -> Result<Option<BezPath>, Budget> {
+    let index = SegmentIndex::new(path);
+    let pieces = boundary_segments(path, &index, fill_rule, PROBE_OFFSET * size, work)?;
+    Ok(join(pieces, JOIN_TOLERANCE * size))
+}
+
+/// The parts of segments with ink on exactly one side under fill_rule, oriented so the ink is
+/// on the side the tangent turned 90° counterclockwise points to.
+///
+/// Where contours overlap, segments that run through ink aren't really outline and would
+/// otherwise stop rays short, so each segment is clipped to where it separates ink from no ink.
+/// Sides are probed at probe_distance from the segment, each probe charged to work.
+pub(crate) fn boundary_segments(
+    path: &BezPath,
+    index: &SegmentIndex,
+    fill_rule: FillRule,
+    probe_distance: f64,
+    work: &Work,
+) -> Result<Vec<PathSeg>, Budget> {
+    let is_inked = |pt: Point| {
+        work.charge_query(index.len());
+        fill_rule.is_inked(index.winding(pt))
+    };
+    // Which side is inked, None if both or neither
+    let inked_side = |seg: &PathSeg, t: f64| {
+        let (pt, tangent) = seg.tangent(t);
+        if is_degenerate(tangent) {
+            return None;
+        }
+        let normal: Vec2 = probe_distance * tangent.normalize().turn_90();
+        match (is_inked(pt + normal), is_inked(pt - normal)) {
+            (true, false) => Some(1.0),
+            (false, true) => Some(-1.0),
+            _ => None,
+        }
+    };
+    let mut boundary = Vec::new();
+    for seg in path.segments() {
+        work.check()?;
+        let is_boundary = |t: f64| inked_side(&seg, t).is_some();
+        let mut push = |t0: f64, t1: f64| {
+            let piece = if t0 == 0.0 && t1 == 1.0 {
+                seg
+            } else {
+                seg.subsegment(t0..t1)
+            };
+            boundary.push(match inked_side(&seg, (t0 + t1) / 2.0) {
+                Some(side) if side < 0.0 => piece.reverse(),
+                _ => piece,
+            });
+        };
+
+        // Sample the middle of equal pieces, bisecting between samples that disagree to find
+        // where the segment enters or leaves ink
+        let samples = (0..BOUNDARY_SAMPLES)
+            .map(|i| (i as f64 + 0.5) / BOUNDARY_SAMPLES as f64)
+            .collect::<Vec<_>>();
+        let mut start = is_boundary(samples[0]).then_some(0.0);
+        for pair in samples.windows(2) {
+            let was = start.is_some();
+            if is_boundary(pair[1]) == was {
+                continue;
+            }
+            let (mut lo, mut hi) = (pair[0], pair[1]);
+            for _ in 0..BOUNDARY_BISECTIONS {
+                let mid = (lo + hi) / 2.0;
+                if is_boundary(mid) == was {
+                    lo = mid;
+                } else {
+                    hi = mid;
+                }
+            }
+            let t = (lo + hi) / 2.0;
+            match start.take() {
+                Some(t0) => push(t0, t),
+                None => start = Some(t),
+            }
+        }
+        if let Some(t0) = start {
+            push(t0, 1.0);
+        }
+    }
+    Ok(boundary)
+}
+
+/// Join pieces of outline end to start into closed contours, None if any piece is left open.
+///
+/// Pieces are first split where another ends on them so that stretches of outline two contours
+/// share, e.g. bars meeting flush at a corner, become identical pieces only one of which is kept.
+fn join(pieces: Vec<PathSeg>, tolerance: f64) -> Option<BezPath> {
+    let near = |a: Point, b: Point| (a - b).hypot() <= tolerance;
+    let ends = pieces
+        .iter()
+        .flat_map(|p| [p.start(), p.end()])
+        .collect::<Vec<_>>();
+    let mut split = Vec::new();
+    for piece in pieces {
+        let mut ts = ends
+            .iter()
+            .filter(|pt| !near(**pt, piece.start()) && !near(**pt, piece.end()))
+            .filter_map(|pt| {
+                let nearest = piece.nearest(*pt, 1e-9);
+                (nearest.distance_sq <= tolerance * tolerance).then_some(nearest.t)
+            })
+            .collect::<Vec<_>>();
+        if ts.is_empty() {
+            split.push(piece);
+            continue;
+        }
+        ts.sort_by(f64::total_cmp);
+        ts.push(1.0);
+        let mut t0 = 0.0;
+        for t in ts {
+            split.push(piece.subsegment(t0..t));
+            t0 = t;
+        }
+    }
+    let mut unique: Vec<PathSeg> = Vec::new();
+    for piece in split {
+        if !unique.iter().any(|u| {
+            near(u.start(), piece.start())
+                && near(u.end(), piece.end())
+                && near(u.eval(0.5), piece.eval(0.5))
+        }) {
+            unique.push(piece);
+        }
+    }
+
+    let mut joined = BezPath::new();
+    let mut unused = unique;
+    while !unused.is_empty() {
+        let mut contour = vec![unused.remove(0)];
+        let start = contour[0].start();
+        loop {
+            let end = contour[contour.len() - 1].end();
+            if near(end, start) {
+                break;
+            }
+            let next = unused
+                .iter()
+                .enumerate()
+                .map(|(i, p)| (i, (p.start() - end).hypot()))
+                .filter(|(_, distance)| *distance <= tolerance)
+                .min_by(|a, b| a.1.total_cmp(&b.1))?
+                .0;
+            let piece = unused.remove(next);
+            // Lines that were split, e.g. where another contour crossed, are joined back up
+            match (contour.last_mut(), piece) {
+                (Some(PathSeg::Line(last)), PathSeg::Line(line))
+                    if continues(*last, line, tolerance) =>
+                {
+                    last.p1 = line.p1
+                }
+                _ => contour.push(piece),
+            }
+        }
+        if let (Some(PathSeg::Line(last)), PathSeg::Line(first)) = (contour.last(), contour[0])
+            && contour.len() > 1
+            && continues(*last, first, tolerance)
+        {
+            contour[0] = PathSeg::Line(Line::new(last.p0, first.p1));
+            contour.pop();
+        }
+
+        let start = contour[0].start();
+        joined.move_to(start);
+        for piece in contour {
+            match piece {
+                PathSeg::Line(line) => joined.line_to(line.p1),
+                PathSeg::Quad(quad) => joined.quad_to(quad.p1, quad.p2),
+                PathSeg::Cubic(cubic) => joined.curve_to(cubic.p1, cubic.p2, cubic.p3),
+            }
+        }
+        // Close exactly rather than with a sliver of a line
+        if let Some(PathEl::LineTo(p) | PathEl::QuadTo(_, p) | PathEl::CurveTo(_, _, p)) =
+            joined.elements_mut().last_mut()
+        {
+            *p = start;
+        }
+        joined.close_path();
+    }
+    Some(joined)
+}
+
+/// Whether next carries straight on from line
+fn continues(line: Line, next: Line, tolerance: f64) -> bool {
+    (next.p1 - next.p0).dot(line.p1 - line.p0) > 0.0
+        && Line::new(line.p0, next.p1)
+            .nearest(line.p1, 1e-9)
+            .distance_sq
+            <= tolerance * tolerance
+}
+
+fn split(path: &BezPath, size: f64) -> Vec<Contour> {
+    let mut paths: Vec<BezPath> = Vec::new();
+    for el in path.elements() {
+        if matches!(el, PathEl::MoveTo(..)) || paths.is_empty() {
+            paths.push(BezPath::new());
+        }
+        paths.last_mut().expect("A contour").push(*el);
+    }
+    paths
+        .into_iter()
+        .map(|path| {
+            let area = path.area();
+            // Probe just inside each segment so contours that merely abut don't overlap
+            let probes = path
+                .segments()
+                .flat_map(|seg| {
+                    (0..CONTOUR_PROBES).filter_map(move |i| {
+                        let (pt, tangent) = seg.tangent((i as f64 + 0.5) / CONTOUR_PROBES as f64);
+                        if is_degenerate(tangent) || area == 0.0 {
+                            return None;
+                        }
+                        let inward = direction(area) as f64 * tangent.normalize().turn_90();
+                        Some(pt + PROBE_OFFSET * size * inward)
+                    })
+                })
+                .collect();
+            Contour {
+                path,
+                area,
+                probes,
+                depth: 0,
+                winding: 0,
+            }
+        })
+        .collect()
+}
+
+/// The winding a contour contributes inside itself, by the sign of its area
+fn direction(area: f64) -> i32 {
+    if area > 0.0 {
+        1
+    } else if area < 0.0 {
+        -1
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::{BezPath, Circle, PathEl, Rect, Shape};
+
+    use crate::{MeasureOptions, budget::Work};
+
+    use super::{FillRule, Normalized};
+
+    fn normalize(path: &BezPath, fill_rule: FillRule, reverse_video_area: f64) -> Normalized {
+        let work = Work::new(&MeasureOptions::default(), path.segments().count());
+        super::normalize(path, fill_rule, reverse_video_area, &work).unwrap()
+    }
+
+    /// A ring drawn like the circle of NewCM10 ® with a 40 unit square counter whose sides have
+    /// midpoints level with the left and right ends of the ring
+    fn ring_around_square() -> BezPath {
+        let mut path = BezPath::new();
+        path.move_to((683.0, -342.0));
+        path.curve_to((683.0, -530.0), (530.0, -683.0), (342.0, -683.0));
+        path.curve_to((153.0, -683.0), (0.0, -530.0), (0.0, -342.0));
+        path.curve_to((0.0, -153.0), (153.0, 0.0), (342.0, 0.0));
+        path.curve_to((530.0, 0.0), (683.0, -153.0), (683.0, -342.0));
+        path.close_path();
+        path.move_to((322.0, -362.0));
+        path.line_to((362.0, -362.0));
+        path.line_to((362.0, -322.0));
+        path.line_to((322.0, -322.0));
+        path.close_path();
+        path
+    }
+
+    /// A circle of radius r around (500, 500) whose signed area has the sign of direction
+    fn circle(r: f64, direction: f64) -> BezPath {
+        let path = BezPath::from_vec(Circle::new((500.0, 500.0), r).path_elements(0.1).collect());
+        if path.area().signum() == direction {
+            path
+        } else {
+            path.reverse_subpaths()
+        }
+    }
+
+    #[test]
+    fn heavy_o_is_not_reverse_video() {
+        let mut path = circle(500.0, 1.0);
+        path.extend(circle(100.0, -1.0).iter());
+        let normalized = normalize(&path, FillRule::NonZero, 0.0);
+
+        assert!(normalized.report.is_clean(), "{}", normalized.report);
+        assert_eq!(1, normalized.counters.len());
+    }
+
+    #[test]
+    fn box_around_o_is_reverse_video() {
+        let mut path = Rect::new(0.0, 0.0, 1000.0, 1000.0).to_path(0.1);
+        let direction = path.area().signum();
+        path.extend(circle(300.0, -direction).iter());
+        path.extend(circle(150.0, direction).iter());
+        let normalized = normalize(&path, FillRule::NonZero, 0.0);
+
+        assert!(normalized.report.inverted, "{}", normalized.report);
+        assert_eq!(0, normalized.report.misoriented);
+        assert_eq!(1, normalized.counters.len());
+    }
+
+    #[test]
+    fn misoriented_counter_is_inked_under_nonzero() {
+        let mut path = circle(500.0, 1.0);
+        path.extend(circle(100.0, 1.0).iter());
+
+        // The counter renders inked, all that's left is the outside of the o
+        let normalized = normalize(&path, FillRule::NonZero, f64::INFINITY);
+        assert_eq!(1, normalized.report.misoriented, "{}", normalized.report);
+        assert!(!normalized.report.unmerged);
+        assert!(normalized.counters.is_empty());
+        assert_eq!(circle(500.0, 1.0), normalized.path);
+
+        // Direction is irrelevant under even-odd
+        let normalized = normalize(&path, FillRule::EvenOdd, f64::INFINITY);
+        assert!(normalized.report.is_clean(), "{}", normalized.report);
+        assert_eq!(1, normalized.counters.len());
+    }
+
+    #[test]
+    fn misoriented_outer_contour_is_reversed() {
+        let mut path = Rect::new(0.0, 0.0, 100.0, 100.0).to_path(0.1);
+        path.extend(
+            Rect::new(200.0, 0.0, 300.0, 100.0)
+                .to_path(0.1)
+                .reverse_subpaths(),
+        );
+        let normalized = normalize(&path, FillRule::NonZero, f64::INFINITY);
+
+        assert_eq!(1, normalized.report.misoriented, "{}", normalized.report);
+        assert_eq!(20000.0, normalized.path.area());
+    }
+
+    #[test]
+    fn box_around_ink_is_not_reverse_video() {
+        // A counter drawn the same way as the box inks, the whole box is solid
+        let mut path = Rect::new(0.0, 0.0, 800.0, 1000.0).to_path(0.1);
+        path.extend(Rect::new(200.0, 50.0, 600.0, 950.0).to_path(0.1).iter());
+        let normalized = normalize(&path, FillRule::NonZero, 0.0);
+
+        assert!(!normalized.report.inverted, "{}", normalized.report);
+        assert_eq!(1, normalized.report.misoriented);
+        assert!(normalized.counters.is_empty());
+        assert_eq!(800.0 * 1000.0, normalized.path.area());
+    }
+
+    #[test]
+    fn overlaps_are_merged() {
+        // A plus drawn as two bars
+        let mut path = Rect::new(0.0, 400.0, 1000.0, 600.0).to_path(0.1);
+        path.extend(Rect::new(400.0, 0.0, 600.0, 1000.0).to_path(0.1).iter());
+        let normalized = normalize(&path, FillRule::NonZero, f64::INFINITY);
+
+        assert_eq!(2, normalized.report.overlapping, "{}", normalized.report);
+        assert!(!normalized.report.unmerged);
+        assert_eq!(
+            1,
+            normalized
+                .path
+                .elements()
+                .iter()
+                .filter(|el| matches!(el, PathEl::MoveTo(_)))
+                .count()
+        );
+        assert_eq!(12, normalized.path.segments().count());
+        let area = normalized.path.area();
+        assert!(
+            (2.0 * 200.0 * 1000.0 - 200.0 * 200.0 - area).abs() < 0.01,
+            "{area}"
+        );
+    }
+
+    #[test]
+    fn abutting_contours_do_not_overlap() {
+        // An H drawn as three rectangles that touch but don't overlap
+        let mut path = BezPath::new();
+        for rect in [
+            Rect::new(0.0, 0.0, 100.0, 700.0),
+            Rect::new(100.0, 300.0, 400.0, 400.0),
+            Rect::new(400.0, 0.0, 500.0, 700.0),
+        ] {
+            path.extend(rect.to_path(0.1).iter());
+        }
+        let normalized = normalize(&path, FillRule::NonZero, f64::INFINITY);
+
+        assert!(normalized.report.is_clean(), "{}", normalized.report);
+        assert_eq!(path, normalized.path);
+    }
+
+    #[test]
+    fn probe_level_with_vertex_of_container() {
+        let path = ring_around_square();
+        let normalized = normalize(&path, FillRule::NonZero, f64::INFINITY);
+
+        assert!(normalized.report.is_clean(), "{}", normalized.report);
+        assert_eq!(1, normalized.counters.len());
+    }
+}
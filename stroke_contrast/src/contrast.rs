@@ -0,0 +1,163 @@
+use std::fmt::{self, Display};
+
+use kurbo::{Circle, Line};
+
+use crate::{RibOrientation, WidthDistribution};
+
+/// Coarse description of the difference between thin and thick strokes
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ContrastClass {
+    /// Strokes are essentially all the same width
+    #[default]
+    Monoline,
+    Low,
+    Medium,
+    High,
+    /// Horizontal strokes are thicker than vertical ones, e.g. a Western or Italian typeface
+    Reverse,
+}
+
+impl Display for ContrastClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ContrastClass::Monoline => "monoline",
+            ContrastClass::Low => "low",
+            ContrastClass::Medium => "medium",
+            ContrastClass::High => "high",
+            ContrastClass::Reverse => "reverse",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Stroke contrast: how thin the thin strokes are relative to the thick ones
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Contrast {
+    /// Thin / thick, 1.0 for monoline, approaching 0 as contrast increases.
+    ///
+    /// The 5th over the 95th percentile rib width, so a single rib cutting a corner or
+    /// spanning a junction doesn't change the class.
+    pub ratio: f64,
+    pub class: ContrastClass,
+}
+
+impl Contrast {
+    pub fn new(distribution: &WidthDistribution, ribs: &[(Line, Circle)]) -> Self {
+        let ratio = if distribution.p95 > 0.0 {
+            (distribution.p5 / distribution.p95).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        let class = match ratio {
+            r if r >= 0.8 => ContrastClass::Monoline,
+            _ if is_reverse(ribs) => ContrastClass::Reverse,
+            r if r >= 0.5 => ContrastClass::Low,
+            r if r >= 0.3 => ContrastClass::Medium,
+            _ => ContrastClass::High,
+        };
+        Self { ratio, class }
+    }
+}
+
+/// True if horizontal strokes are clearly thicker than vertical ones
+///
+/// A horizontal stroke is measured by a near-vertical rib and vice versa.
+fn is_reverse(ribs: &[(Line, Circle)]) -> bool {
+    let mean_width = |orientation| {
+        let widths = ribs
+            .iter()
+            .filter(|(rib, _)| RibOrientation::of(rib) == orientation)
+            .map(|(_, circle)| 2.0 * circle.radius)
+            .collect::<Vec<_>>();
+        (!widths.is_empty()).then(|| widths.iter().sum::<f64>() / widths.len() as f64)
+    };
+    let horizontal_strokes = mean_width(RibOrientation::NearVertical);
+    let vertical_strokes = mean_width(RibOrientation::NearHorizontal);
+    match (horizontal_strokes, vertical_strokes) {
+        (Some(horizontal), Some(vertical)) => horizontal > 1.1 * vertical,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::{Circle, Line, Point};
+
+    use crate::WidthDistribution;
+
+    use super::{Contrast, ContrastClass};
+
+    /// A rib width wide, horizontal to measure a vertical stem or vertical to measure a bar
+    fn rib(width: f64, horizontal: bool) -> (Line, Circle) {
+        let end = if horizontal {
+            Point::new(width, 0.0)
+        } else {
+            Point::new(0.0, width)
+        };
+        let rib = Line::new(Point::ORIGIN, end);
+        (rib, Circle::new(rib.midpoint(), width / 2.0))
+    }
+
+    /// Ten vertical stems thick wide and ten horizontal bars thin wide, enough that the 5th and
+    /// 95th percentiles are thin and thick
+    fn contrast(thin: f64, thick: f64) -> Contrast {
+        let ribs = (0..10)
+            .flat_map(|_| [rib(thick, true), rib(thin, false)])
+            .collect::<Vec<_>>();
+        let widths = ribs.iter().map(|(_, c)| 2.0 * c.radius).collect();
+        Contrast::new(&WidthDistribution::new(widths), &ribs)
+    }
+
+    #[test]
+    fn classes_change_at_boundaries() {
+        for (thin, class) in [
+            (100.0, ContrastClass::Monoline),
+            (80.0, ContrastClass::Monoline),
+            (79.0, ContrastClass::Low),
+            (50.0, ContrastClass::Low),
+            (49.0, ContrastClass::Medium),
+            (30.0, ContrastClass::Medium),
+            (29.0, ContrastClass::High),
+            (1.0, ContrastClass::High),
+        ] {
+            let contrast = contrast(thin, 100.0);
+            assert!((thin / 100.0 - contrast.ratio).abs() < 1e-9, "{contrast:?}");
+            assert_eq!(class, contrast.class, "{thin} {contrast:?}");
+        }
+    }
+
+    #[test]
+    fn ratio_ignores_outliers() {
+        // One stray hairline rib among a hundred near-monoline ones
+        let mut widths = (0..100).map(|i| 90.0 + i as f64 / 10.0).collect::<Vec<_>>();
+        widths.push(1.0);
+        let ribs = widths.iter().map(|w| rib(*w, true)).collect::<Vec<_>>();
+        let contrast = Contrast::new(&WidthDistribution::new(widths), &ribs);
+        assert_eq!(ContrastClass::Monoline, contrast.class, "{contrast:?}");
+    }
+
+    #[test]
+    fn thick_bars_are_reverse() {
+        // Thin vertical stems, measured by horizontal ribs, and thick horizontal bars
+        let ribs = [rib(20.0, true), rib(100.0, false)];
+        let contrast = Contrast::new(&WidthDistribution::new(vec![20.0, 100.0]), &ribs);
+        assert_eq!(ContrastClass::Reverse, contrast.class);
+
+        // Bars must be clearly thicker than the mean stem to be reverse
+        let ribs = [rib(30.0, true), rib(100.0, true), rib(70.0, false)];
+        let contrast = Contrast::new(&WidthDistribution::new(vec![30.0, 70.0, 100.0]), &ribs);
+        assert_eq!(ContrastClass::Medium, contrast.class);
+
+        // Monoline beats reverse
+        let ribs = [rib(85.0, true), rib(100.0, false)];
+        let contrast = Contrast::new(&WidthDistribution::new(vec![85.0, 100.0]), &ribs);
+        assert_eq!(ContrastClass::Monoline, contrast.class);
+    }
+
+    #[test]
+    fn no_width_is_monoline() {
+        let contrast = Contrast::new(&WidthDistribution::default(), &[]);
+        assert_eq!(1.0, contrast.ratio);
+        assert_eq!(ContrastClass::Monoline, contrast.class);
+    }
+}
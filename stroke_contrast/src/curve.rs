@@ -0,0 +1,238 @@
+use std::fmt::{self, Display};
+
+use fontdrasil::coords::UserLocation;
+use skrifa::Tag;
+
+use crate::csv_fragment;
+
+/// Relative decrease in width along an axis we tolerate before calling it an anomaly.
+///
+/// Measurements are noisy, a rib landing a little differently shouldn't be reported.
+const MONOTONIC_TOLERANCE: f64 = 0.01;
+
+/// Stroke widths at one value of the axis
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CurvePoint {
+    pub value: f64,
+    pub min_width: f64,
+    pub max_width: f64,
+}
+
+/// Which width an anomaly concerns
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CurveMetric {
+    MinWidth,
+    MaxWidth,
+}
+
+impl Display for CurveMetric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CurveMetric::MinWidth => f.write_str("min width"),
+            CurveMetric::MaxWidth => f.write_str("max width"),
+        }
+    }
+}
+
+/// A stroke that got thinner as the axis value increased, typically a measurement failure
+/// or a broken master
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CurveAnomaly {
+    pub metric: CurveMetric,
+    pub from: CurvePoint,
+    pub to: CurvePoint,
+}
+
+/// Stroke width versus the value of one axis, e.g. wght, with every other axis fixed
+#[derive(Debug, Clone, PartialEq)]
+pub struct WidthCurve {
+    pub axis: Tag,
+    /// Where every other axis is, empty if nothing else varies
+    pub others: UserLocation,
+    /// Ascending by value
+    pub points: Vec<CurvePoint>,
+    pub anomalies: Vec<CurveAnomaly>,
+}
+
+impl WidthCurve {
+    /// Build one curve along axis for each distinct position of the other axes.
+    ///
+    /// measurements are (location, min width, max width). Locations that don't specify axis are
+    /// ignored, as are curves with fewer than two points.
+    pub fn along(axis: Tag, measurements: &[(UserLocation, f64, f64)]) -> Vec<WidthCurve> {
+        let mut curves: Vec<WidthCurve> = Vec::new();
+        for (loc, min_width, max_width) in measurements {
+            let Some(value) = loc.get(axis) else {
+                continue;
+            };
+            let mut others = loc.clone();
+            others.remove(axis);
+            let point = CurvePoint {
+                value: value.to_f64(),
+                min_width: *min_width,
+                max_width: *max_width,
+            };
+            match curves.iter_mut().find(|c| c.others == others) {
+                Some(curve) => curve.points.push(point),
+                None => curves.push(WidthCurve {
+                    axis,
+                    others,
+                    points: vec![point],
+                    anomalies: Vec::new(),
+                }),
+            }
+        }
+        curves.retain(|c| c.points.len() > 1);
+        for curve in curves.iter_mut() {
+            curve.points.sort_by(|a, b| a.value.total_cmp(&b.value));
+            curve.anomalies = anomalies(&curve.points);
+        }
+        curves
+    }
+
+    pub fn is_monotonic(&self) -> bool {
+        self.anomalies.is_empty()
+    }
+}
+
+impl Display for WidthCurve {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} curve", self.axis)?;
+        if self.others.iter().next().is_some() {
+            write!(f, " at {}", csv_fragment(&self.others))?;
+        }
+        for point in self.points.iter() {
+            write!(
+                f,
+                "\n  {}={:.2} width {:.2}..{:.2}",
+                self.axis, point.value, point.min_width, point.max_width
+            )?;
+        }
+        for anomaly in self.anomalies.iter() {
+            write!(
+                f,
+                "\n  non-monotonic {}: {:.2} at {}={:.2} but {:.2} at {}={:.2}",
+                anomaly.metric,
+                width(&anomaly.from, anomaly.metric),
+                self.axis,
+                anomaly.from.value,
+                width(&anomaly.to, anomaly.metric),
+                self.axis,
+                anomaly.to.value
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn width(point: &CurvePoint, metric: CurveMetric) -> f64 {
+    match metric {
+        CurveMetric::MinWidth => point.min_width,
+        CurveMetric::MaxWidth => point.max_width,
+    }
+}
+
+/// Each step along the curve where a width drops by more than we tolerate
+fn anomalies(points: &[CurvePoint]) -> Vec<CurveAnomaly> {
+    let mut anomalies = Vec::new();
+    for pair in points.windows(2) {
+        for metric in [CurveMetric::MinWidth, CurveMetric::MaxWidth] {
+            let (from, to) = (width(&pair[0], metric), width(&pair[1], metric));
+            if to < from * (1.0 - MONOTONIC_TOLERANCE) {
+                anomalies.push(CurveAnomaly {
+                    metric,
+                    from: pair[0],
+                    to: pair[1],
+                });
+            }
+        }
+    }
+    anomalies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CurveMetric, WidthCurve};
+    use crate::test_util::{WDTH, WGHT, loc};
+
+    #[test]
+    fn thinning_beyond_tolerance_is_flagged() {
+        let curves = WidthCurve::along(
+            WGHT,
+            &[
+                (loc(&[(WGHT, 700.0)]), 90.0, 180.0),
+                (loc(&[(WGHT, 100.0)]), 10.0, 20.0),
+                (loc(&[(WGHT, 400.0)]), 40.0, 80.0),
+            ],
+        );
+        assert_eq!(1, curves.len());
+        assert!(curves[0].is_monotonic(), "{:?}", curves[0]);
+        assert_eq!(
+            vec![100.0, 400.0, 700.0],
+            curves[0].points.iter().map(|p| p.value).collect::<Vec<_>>()
+        );
+
+        // Max width drops 5% from 400 to 700
+        let curves = WidthCurve::along(
+            WGHT,
+            &[
+                (loc(&[(WGHT, 400.0)]), 40.0, 80.0),
+                (loc(&[(WGHT, 700.0)]), 90.0, 76.0),
+            ],
+        );
+        let anomalies = &curves[0].anomalies;
+        assert_eq!(1, anomalies.len(), "{anomalies:?}");
+        assert_eq!(CurveMetric::MaxWidth, anomalies[0].metric);
+        assert_eq!(
+            (400.0, 700.0),
+            (anomalies[0].from.value, anomalies[0].to.value)
+        );
+    }
+
+    #[test]
+    fn thinning_within_tolerance_is_not_flagged() {
+        // Both widths drop 0.5%
+        let curves = WidthCurve::along(
+            WGHT,
+            &[
+                (loc(&[(WGHT, 400.0)]), 40.0, 80.0),
+                (loc(&[(WGHT, 700.0)]), 39.8, 79.6),
+            ],
+        );
+        assert!(curves[0].is_monotonic(), "{:?}", curves[0]);
+    }
+
+    #[test]
+    fn curves_split_by_other_axes() {
+        let curves = WidthCurve::along(
+            WGHT,
+            &[
+                (loc(&[(WGHT, 100.0), (WDTH, 100.0)]), 10.0, 20.0),
+                (loc(&[(WGHT, 100.0), (WDTH, 75.0)]), 8.0, 16.0),
+                (loc(&[(WGHT, 900.0), (WDTH, 100.0)]), 90.0, 180.0),
+                (loc(&[(WGHT, 900.0), (WDTH, 75.0)]), 6.0, 150.0),
+                // Alone at its wdth, and without wght, so neither makes a curve
+                (loc(&[(WGHT, 400.0), (WDTH, 50.0)]), 40.0, 80.0),
+                (loc(&[(WDTH, 100.0)]), 40.0, 80.0),
+            ],
+        );
+        assert_eq!(2, curves.len(), "{curves:?}");
+        let [wide, narrow] = [100.0, 75.0].map(|wdth| {
+            curves
+                .iter()
+                .find(|c| c.others == loc(&[(WDTH, wdth)]))
+                .unwrap()
+        });
+        assert_eq!(2, wide.points.len());
+        assert!(wide.is_monotonic(), "{wide:?}");
+        assert_eq!(2, narrow.points.len());
+        assert_eq!(
+            vec![CurveMetric::MinWidth],
+            narrow
+                .anomalies
+                .iter()
+                .map(|a| a.metric)
+                .collect::<Vec<_>>()
+        );
+    }
+}
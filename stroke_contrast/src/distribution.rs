@@ -0,0 +1,129 @@
+/// Summary statistics of stroke widths, typically the diameters of every rib found.
+///
+/// Min and max are at the mercy of a single bad rib, percentiles are much more robust.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WidthDistribution {
+    /// Every width, ascending
+    pub widths: Vec<f64>,
+    pub mean: f64,
+    pub median: f64,
+    pub p5: f64,
+    pub p95: f64,
+    pub std_dev: f64,
+}
+
+/// Counts of widths in equal width bins starting at min
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Histogram {
+    pub min: f64,
+    pub bin_width: f64,
+    pub counts: Vec<usize>,
+}
+
+impl WidthDistribution {
+    pub fn new(mut widths: Vec<f64>) -> Self {
+        if widths.is_empty() {
+            return Default::default();
+        }
+        widths.sort_by(f64::total_cmp);
+        let count = widths.len() as f64;
+        let mean = widths.iter().sum::<f64>() / count;
+        let variance = widths.iter().map(|w| (w - mean).powi(2)).sum::<f64>() / count;
+        let mut distribution = Self {
+            widths,
+            mean,
+            std_dev: variance.sqrt(),
+            ..Default::default()
+        };
+        distribution.median = distribution.percentile(50.0);
+        distribution.p5 = distribution.percentile(5.0);
+        distribution.p95 = distribution.percentile(95.0);
+        distribution
+    }
+
+    /// The width below which percentile (0..=100) percent of widths fall, linearly interpolated
+    pub fn percentile(&self, percentile: f64) -> f64 {
+        let Some(last) = self.widths.len().checked_sub(1) else {
+            return 0.0;
+        };
+        let rank = percentile.clamp(0.0, 100.0) / 100.0 * last as f64;
+        let (lo, hi) = (rank.floor() as usize, rank.ceil() as usize);
+        let fract = rank - lo as f64;
+        self.widths[lo] + fract * (self.widths[hi] - self.widths[lo])
+    }
+
+    pub fn histogram(&self, bins: usize) -> Histogram {
+        let (Some(min), Some(max)) = (self.widths.first(), self.widths.last()) else {
+            return Default::default();
+        };
+        if bins == 0 {
+            return Default::default();
+        }
+        // A single distinct width still gets a non-zero bin
+        let bin_width = ((max - min) / bins as f64).max(f64::EPSILON);
+        let mut counts = vec![0; bins];
+        for width in self.widths.iter() {
+            let bin = (((width - min) / bin_width) as usize).min(bins - 1);
+            counts[bin] += 1;
+        }
+        Histogram {
+            min: *min,
+            bin_width,
+            counts,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Histogram, WidthDistribution};
+
+    #[test]
+    fn percentile_interpolates_between_widths() {
+        let distribution = WidthDistribution::new(vec![40.0, 10.0, 30.0, 20.0, 50.0]);
+        assert_eq!(10.0, distribution.percentile(0.0));
+        assert_eq!(30.0, distribution.percentile(50.0));
+        assert_eq!(50.0, distribution.percentile(100.0));
+        // Rank 0.2 of the way from 10 to 20
+        assert!((distribution.percentile(5.0) - 12.0).abs() < 1e-9);
+        assert!((distribution.percentile(95.0) - 48.0).abs() < 1e-9);
+        assert_eq!(distribution.median, distribution.percentile(50.0));
+    }
+
+    #[test]
+    fn percentile_clamps_and_handles_degenerate_input() {
+        let distribution = WidthDistribution::new(vec![7.0]);
+        assert_eq!(7.0, distribution.percentile(-10.0));
+        assert_eq!(7.0, distribution.percentile(150.0));
+        assert_eq!(0.0, WidthDistribution::new(Vec::new()).percentile(50.0));
+    }
+
+    #[test]
+    fn histogram_counts_per_bin() {
+        let distribution = WidthDistribution::new(vec![10.0, 12.0, 19.9, 20.0, 35.0, 50.0, 50.0]);
+        assert_eq!(
+            Histogram {
+                min: 10.0,
+                bin_width: 10.0,
+                counts: vec![3, 1, 1, 2],
+            },
+            distribution.histogram(4)
+        );
+    }
+
+    #[test]
+    fn histogram_of_degenerate_input() {
+        // A single distinct width lands in the first bin
+        let histogram = WidthDistribution::new(vec![7.0, 7.0]).histogram(3);
+        assert_eq!(vec![2, 0, 0], histogram.counts);
+        assert!(histogram.bin_width > 0.0);
+        assert_eq!(
+            Histogram::default(),
+            WidthDistribution::new(vec![7.0]).histogram(0)
+        );
+        assert_eq!(
+            Histogram::default(),
+            WidthDistribution::new(Vec::new()).histogram(3)
+        );
+    }
+}
@@ -0,0 +1,74 @@
+use std::fmt::{self, Display};
+
+use kurbo::Point;
+use skrifa::{GlyphId, outline::DrawError};
+
+use crate::{Budget, WidthCandidates};
+
+/// Reasons we might fail to read stroke widths
+#[derive(Debug)]
+pub enum StrokeContrastError {
+    /// The font bytes didn't parse. Holds the parse error message, harfruzz and skrifa
+    /// use different versions of read-fonts so there isn't a single error type to keep.
+    FontParse(String),
+    /// The font's cmap has no glyph for the character
+    UnmappedCharacter(char),
+    /// Shaping the text produced no glyphs or included .notdef
+    ShapedToNotdef(String),
+    /// No glyph has this name in the post or CFF table
+    UnknownGlyphName(String),
+    /// The glyph id isn't in the font
+    MissingGlyph(GlyphId),
+    /// The glyph exists but we couldn't draw it
+    DrawFailure(GlyphId, DrawError),
+    /// The drawn glyph(s) enclose no area
+    EmptyOutline,
+    /// The center of mass is inked, rays cast from it won't find strokes, and there are no
+    /// counters to cast rays from instead
+    InkedCentroid(Point),
+    /// Measurement completed but found no usable ribs
+    NoRibsFound,
+    /// Measurement ran out of budget. If any ribs were found before it did they are in partial.
+    BudgetExceeded {
+        budget: Budget,
+        partial: Option<Box<WidthCandidates>>,
+    },
+}
+
+impl Display for StrokeContrastError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StrokeContrastError::FontParse(e) => write!(f, "Unable to parse font: {e}"),
+            StrokeContrastError::UnmappedCharacter(ch) => {
+                write!(f, "No glyph is mapped for {ch:?} (U+{:04X})", *ch as u32)
+            }
+            StrokeContrastError::ShapedToNotdef(text) => {
+                write!(f, "Shaping {text:?} produced .notdef")
+            }
+            StrokeContrastError::UnknownGlyphName(name) => write!(f, "No glyph named {name:?}"),
+            StrokeContrastError::MissingGlyph(gid) => write!(f, "No outline for {gid}"),
+            StrokeContrastError::DrawFailure(gid, e) => write!(f, "Unable to draw {gid}: {e}"),
+            StrokeContrastError::EmptyOutline => write!(f, "The outline encloses no area"),
+            StrokeContrastError::InkedCentroid(pt) => write!(
+                f,
+                "Center of mass ({:.2}, {:.2}) is inked and there are no counters, unable to cast rays",
+                pt.x, pt.y
+            ),
+            StrokeContrastError::NoRibsFound => write!(f, "No ribs found"),
+            StrokeContrastError::BudgetExceeded { budget, partial } => write!(
+                f,
+                "Gave up after {budget}, {} ribs found",
+                partial.as_ref().map(|p| p.ribs.len()).unwrap_or_default()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for StrokeContrastError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StrokeContrastError::DrawFailure(_, e) => Some(e),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,259 @@
+use std::fmt::{self, Display};
+
+use skrifa::{GlyphId, instance::Location};
+
+use crate::{
+    MeasureOptions, SegmentSelection, ShapingOptions, StrokeContrastError, WidthCandidates,
+    WidthReader,
+};
+
+/// How to find a glyph to measure
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GlyphSelector {
+    /// Shape the character, respecting cmap and layout
+    Char(char),
+    /// Shape the text and measure all the glyphs produced as one outline, e.g. a word or a
+    /// base plus marks
+    Text(String),
+    /// A glyph id, bypassing shaping
+    Id(GlyphId),
+    /// A post or CFF glyph name, bypassing shaping
+    Name(String),
+}
+
+impl From<char> for GlyphSelector {
+    fn from(ch: char) -> Self {
+        GlyphSelector::Char(ch)
+    }
+}
+
+impl Display for GlyphSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GlyphSelector::Char(ch) => write!(f, "'{ch}'"),
+            GlyphSelector::Text(text) => write!(f, "{text:?}"),
+            GlyphSelector::Id(gid) => write!(f, "{gid}"),
+            GlyphSelector::Name(name) => write!(f, "/{name}"),
+        }
+    }
+}
+
+impl GlyphSelector {
+    /// Shaping only applies to [GlyphSelector::Char] and [GlyphSelector::Text], ids and names
+    /// are already glyphs
+    pub fn width_reader(
+        &self,
+        raw_font: &[u8],
+        shaping: &ShapingOptions,
+        loc: &Location,
+        options: MeasureOptions,
+    ) -> Result<WidthReader, StrokeContrastError> {
+        match self {
+            GlyphSelector::Char(ch) => {
+                WidthReader::with_shaping(raw_font, *ch, shaping, loc, options)
+            }
+            GlyphSelector::Text(text) => {
+                WidthReader::from_text(raw_font, text, shaping, loc, options)
+            }
+            GlyphSelector::Id(gid) => WidthReader::from_glyph_id(raw_font, *gid, loc, options),
+            GlyphSelector::Name(name) => WidthReader::from_glyph_name(raw_font, name, loc, options),
+        }
+    }
+}
+
+/// A successfully measured glyph
+pub struct GlyphMeasurement {
+    pub reader: WidthReader,
+    pub candidates: WidthCandidates,
+}
+
+/// Measurements of several glyphs at a single location
+pub struct GlyphSetMeasurement {
+    /// Per glyph results, in the order requested
+    pub glyphs: Vec<(GlyphSelector, Result<GlyphMeasurement, StrokeContrastError>)>,
+    /// The ribs of every successfully measured glyph pooled into a single stroke profile.
+    ///
+    /// None if no glyph could be measured. Which glyphs succeed can vary by location, use
+    /// [GlyphSetMeasurement::profile_of] to compare the same glyphs across locations.
+    pub profile: Option<WidthCandidates>,
+}
+
+/// Parse a glyph set such as "o n H v" into the characters to measure.
+///
+/// Whitespace is ignored so "onHv" is equivalent. Duplicates are dropped.
+pub fn parse_glyph_set(spec: &str) -> Vec<char> {
+    let mut chars = Vec::new();
+    for ch in spec.chars().filter(|c| !c.is_whitespace()) {
+        if !chars.contains(&ch) {
+            chars.push(ch);
+        }
+    }
+    chars
+}
+
+/// Measure each of glyphs and pool the results.
+///
+/// Failure to measure one glyph doesn't fail the set, e.g. the center of mass of 'v'
+/// is typically inked, so a set can still profile a family from the glyphs that work.
+pub fn measure_glyph_set(
+    raw_font: &[u8],
+    glyphs: &[GlyphSelector],
+    shaping: &ShapingOptions,
+    loc: &Location,
+    method: SegmentSelection,
+    options: MeasureOptions,
+) -> GlyphSetMeasurement {
+    let glyphs = glyphs
+        .iter()
+        .map(|glyph| {
+            let measurement = glyph
+                .width_reader(raw_font, shaping, loc, options)
+                .and_then(|reader| {
+                    let candidates = reader.measure(method)?;
+                    Ok(GlyphMeasurement { reader, candidates })
+                });
+            (glyph.clone(), measurement)
+        })
+        .collect::<Vec<_>>();
+
+    let mut measured = GlyphSetMeasurement {
+        glyphs,
+        profile: None,
+    };
+    let all = measured
+        .glyphs
+        .iter()
+        .map(|(glyph, _)| glyph.clone())
+        .collect::<Vec<_>>();
+    measured.profile = measured.profile_of(&all);
+    measured
+}
+
+impl GlyphSetMeasurement {
+    /// Pool the ribs of those of glyphs that were successfully measured.
+    ///
+    /// Useful to profile the same glyphs at every location when some fail at only a few.
+    /// None if none of them were.
+    pub fn profile_of(&self, glyphs: &[GlyphSelector]) -> Option<WidthCandidates> {
+        let mut rays = Vec::new();
+        let mut ribs = Vec::new();
+        for (_, measurement) in self.glyphs.iter().filter(|(g, _)| glyphs.contains(g)) {
+            if let Ok(measurement) = measurement {
+                rays.extend(measurement.candidates.rays.iter().copied());
+                ribs.extend(measurement.candidates.ribs.iter().copied());
+            }
+        }
+        WidthCandidates::from_ribs(rays, ribs).ok()
+    }
+
+    /// Whether glyph was successfully measured
+    pub fn measured(&self, glyph: &GlyphSelector) -> bool {
+        self.glyphs.iter().any(|(g, m)| g == glyph && m.is_ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::{Circle, Line, Point, Rect, Shape};
+    use skrifa::{GlyphId, instance::Location};
+
+    use crate::{StrokeContrastError, WidthCandidates, WidthReader};
+
+    use super::{GlyphMeasurement, GlyphSelector, GlyphSetMeasurement, parse_glyph_set};
+
+    /// A measurement whose only rib is width wide
+    fn measured(width: f64) -> Result<GlyphMeasurement, StrokeContrastError> {
+        let mut path = Rect::new(0.0, 0.0, 100.0, 100.0).to_path(0.1);
+        path.extend(
+            Rect::new(width, width, 100.0 - width, 100.0 - width)
+                .to_path(0.1)
+                .reverse_subpaths(),
+        );
+        let reader = WidthReader::from_path(path, Default::default())?;
+        let rib = Line::new((0.0, 50.0), (width, 50.0));
+        let candidates = WidthCandidates::from_ribs(
+            vec![rib],
+            vec![(rib, Circle::new(rib.midpoint(), width / 2.0))],
+        )?;
+        Ok(GlyphMeasurement { reader, candidates })
+    }
+
+    fn glyph_set() -> GlyphSetMeasurement {
+        GlyphSetMeasurement {
+            glyphs: vec![
+                ('o'.into(), measured(10.0)),
+                ('n'.into(), measured(30.0)),
+                (
+                    'v'.into(),
+                    Err(StrokeContrastError::InkedCentroid(Point::ORIGIN)),
+                ),
+            ],
+            profile: None,
+        }
+    }
+
+    #[test]
+    fn parse_ignores_whitespace_and_duplicates() {
+        assert_eq!(vec!['o', 'n', 'H', 'v'], parse_glyph_set("o n H v"));
+        assert_eq!(vec!['o', 'n', 'H', 'v'], parse_glyph_set("onHv"));
+        assert_eq!(vec!['o', 'n'], parse_glyph_set(" o\tn o\n"));
+        assert!(parse_glyph_set(" ").is_empty());
+    }
+
+    #[test]
+    fn profile_pools_only_the_glyphs_requested() {
+        let glyph_set = glyph_set();
+        let profile = glyph_set.profile_of(&['o'.into(), 'n'.into()]).unwrap();
+        assert_eq!((10.0, 30.0), (profile.min_width, profile.max_width));
+        assert_eq!(2, profile.ribs.len());
+
+        let profile = glyph_set.profile_of(&['n'.into()]).unwrap();
+        assert_eq!((30.0, 30.0), (profile.min_width, profile.max_width));
+    }
+
+    #[test]
+    fn profile_skips_failures() {
+        let glyph_set = glyph_set();
+        let profile = glyph_set.profile_of(&['o'.into(), 'v'.into()]).unwrap();
+        assert_eq!(1, profile.ribs.len());
+        assert!(glyph_set.profile_of(&['v'.into()]).is_none());
+        assert!(glyph_set.profile_of(&[GlyphSelector::Char('x')]).is_none());
+        assert!(!glyph_set.measured(&'v'.into()));
+        assert!(glyph_set.measured(&'o'.into()));
+    }
+
+    /// An OpenType font with no tables, and so no glyphs
+    fn empty_font() -> Vec<u8> {
+        let mut font = 0x00010000u32.to_be_bytes().to_vec();
+        // numTables, searchRange, entrySelector and rangeShift
+        font.extend([0; 8]);
+        font
+    }
+
+    fn width_reader(glyph: GlyphSelector) -> Result<WidthReader, StrokeContrastError> {
+        glyph.width_reader(
+            &empty_font(),
+            &Default::default(),
+            &Location::default(),
+            Default::default(),
+        )
+    }
+
+    #[test]
+    fn unknown_glyph_name() {
+        match width_reader(GlyphSelector::Name("a.sc".to_string())) {
+            Err(StrokeContrastError::UnknownGlyphName(name)) => assert_eq!("a.sc", name),
+            Err(e) => panic!("{e}"),
+            Ok(_) => panic!("Measured a glyph that isn't there"),
+        }
+    }
+
+    #[test]
+    fn missing_glyph_id() {
+        match width_reader(GlyphSelector::Id(GlyphId::new(1))) {
+            Err(StrokeContrastError::MissingGlyph(gid)) => assert_eq!(GlyphId::new(1), gid),
+            Err(e) => panic!("{e}"),
+            Ok(_) => panic!("Measured a glyph that isn't there"),
+        }
+    }
+}
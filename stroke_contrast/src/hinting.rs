@@ -0,0 +1,317 @@
+//! Stem widths the font's hints claim, to compare with what we measure.
+//!
+//! CFF private dicts say outright what the dominant stems are. TrueType `cvt ` values are
+//! unlabeled, stems are in there somewhere among heights and overshoots, and any measured stem
+//! is usually close to one of them. Telling which are stems means interpreting the hinting
+//! programs so the cvt value nearest each measured stem is reported but never flagged.
+
+use std::fmt::{self, Display};
+
+use skrifa::raw::{
+    TableProvider,
+    tables::postscript::{
+        Index,
+        dict::{Entry, entries},
+    },
+};
+
+use crate::StemWidths;
+
+/// Relative difference between a hinted and a measured stem we consider a disagreement
+const DISAGREEMENT_TOLERANCE: f64 = 0.2;
+
+/// Hinted stem widths, in font units, as found in the font
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HintedStems {
+    /// Dominant horizontal stem, i.e. bar, width from the CFF private dict
+    pub std_hw: Option<f64>,
+    /// Dominant vertical stem width from the CFF private dict
+    pub std_vw: Option<f64>,
+    pub stem_snap_h: Vec<f64>,
+    pub stem_snap_v: Vec<f64>,
+    /// Every positive TrueType control value
+    pub cvt: Vec<f64>,
+}
+
+/// Where a hinted value came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HintSource {
+    StdHW,
+    StdVW,
+    StemSnapH,
+    StemSnapV,
+}
+
+impl Display for HintSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            HintSource::StdHW => "StdHW",
+            HintSource::StdVW => "StdVW",
+            HintSource::StemSnapH => "StemSnapH",
+            HintSource::StemSnapV => "StemSnapV",
+        };
+        f.write_str(name)
+    }
+}
+
+/// The cvt value nearest a measured stem
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NearestCvt {
+    /// Which measured stem, "vertical stem" or "horizontal bar"
+    pub stem: &'static str,
+    /// In font units
+    pub cvt: f64,
+    /// Median measured width, in font units
+    pub measured: f64,
+}
+
+/// A hinted stem that is far from the corresponding measured one
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HintDisagreement {
+    pub source: HintSource,
+    /// The hinted value closest to measured, in font units
+    pub hinted: f64,
+    /// Median measured width, in font units
+    pub measured: f64,
+}
+
+impl HintedStems {
+    pub fn new(font: &skrifa::FontRef) -> Self {
+        let mut hints = HintedStems::default();
+        for private_dict in private_dicts(font) {
+            for entry in entries(private_dict, None) {
+                // CFF2 private dicts may blend, we don't have a location to blend at so give up
+                let Ok(entry) = entry else {
+                    break;
+                };
+                match entry {
+                    Entry::StdHw(v) => hints.std_hw = hints.std_hw.or(Some(v.to_f64())),
+                    Entry::StdVw(v) => hints.std_vw = hints.std_vw.or(Some(v.to_f64())),
+                    Entry::StemSnapH(snaps) => hints
+                        .stem_snap_h
+                        .extend(snaps.values().iter().map(|v| v.to_f64())),
+                    Entry::StemSnapV(snaps) => hints
+                        .stem_snap_v
+                        .extend(snaps.values().iter().map(|v| v.to_f64())),
+                    _ => (),
+                }
+            }
+        }
+        if let Ok(cvt) = font.cvt() {
+            hints.cvt = cvt
+                .iter()
+                .map(|v| v.get() as f64)
+                .filter(|v| *v > 0.0)
+                .collect();
+        }
+        hints
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == HintedStems::default()
+    }
+
+    /// The cvt value nearest the measured vertical stem and horizontal bar, where there are both
+    pub fn nearest_cvt(&self, stems: &StemWidths) -> Vec<NearestCvt> {
+        [
+            ("vertical stem", &stems.vertical_stem),
+            ("horizontal bar", &stems.horizontal_bar),
+        ]
+        .into_iter()
+        .filter_map(|(stem, measured)| {
+            let measured = measured.as_ref()?.median;
+            let cvt = nearest(&self.cvt, measured)?;
+            Some(NearestCvt {
+                stem,
+                cvt,
+                measured,
+            })
+        })
+        .collect()
+    }
+
+    /// Hinted stems that disagree with measured stems by more than we tolerate.
+    ///
+    /// Hints describe the default instance so stems should be measured there.
+    pub fn compare(&self, stems: &StemWidths) -> Vec<HintDisagreement> {
+        let vertical = stems.vertical_stem.as_ref().map(|d| d.median);
+        let horizontal = stems.horizontal_bar.as_ref().map(|d| d.median);
+        let candidates: [(HintSource, &[f64], Option<f64>); 4] = [
+            (HintSource::StdVW, self.std_vw.as_slice(), vertical),
+            (HintSource::StdHW, self.std_hw.as_slice(), horizontal),
+            (HintSource::StemSnapV, &self.stem_snap_v, vertical),
+            (HintSource::StemSnapH, &self.stem_snap_h, horizontal),
+        ];
+        candidates
+            .into_iter()
+            .filter_map(|(source, hinted, measured)| {
+                let measured = measured?;
+                let hinted = nearest(hinted, measured)?;
+                ((hinted - measured).abs() > DISAGREEMENT_TOLERANCE * measured).then_some(
+                    HintDisagreement {
+                        source,
+                        hinted,
+                        measured,
+                    },
+                )
+            })
+            .collect()
+    }
+}
+
+fn nearest(values: &[f64], target: f64) -> Option<f64> {
+    values
+        .iter()
+        .copied()
+        .min_by(|a, b| (a - target).abs().total_cmp(&(b - target).abs()))
+}
+
+/// The private dict of every font dict in the CFF or CFF2 table, if any
+fn private_dicts<'a>(font: &skrifa::FontRef<'a>) -> Vec<&'a [u8]> {
+    let (table, top_dict, is_cff2) = if let Ok(cff) = font.cff() {
+        let Ok(top_dict) = cff.top_dicts().get(0) else {
+            return Vec::new();
+        };
+        (cff.offset_data().as_bytes(), top_dict, false)
+    } else if let Ok(cff2) = font.cff2() {
+        (cff2.offset_data().as_bytes(), cff2.top_dict_data(), true)
+    } else {
+        return Vec::new();
+    };
+
+    // A CID-keyed or CFF2 font has a font dict per FD, each with its own private dict
+    let mut font_dicts = vec![top_dict];
+    for entry in entries(top_dict, None).filter_map(Result::ok) {
+        if let Entry::FdArrayOffset(offset) = entry
+            && let Some(data) = table.get(offset..)
+            && let Ok(fd_array) = Index::new(data, is_cff2)
+        {
+            font_dicts.extend((0..fd_array.count() as usize).filter_map(|i| fd_array.get(i).ok()));
+        }
+    }
+    font_dicts
+        .into_iter()
+        .flat_map(|font_dict| entries(font_dict, None).filter_map(Result::ok))
+        .filter_map(|entry| match entry {
+            Entry::PrivateDictRange(range) => table.get(range),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{StemWidths, WidthDistribution};
+
+    use super::{HintDisagreement, HintSource, HintedStems, NearestCvt};
+
+    fn stems(vertical: Option<f64>, horizontal: Option<f64>) -> StemWidths {
+        StemWidths {
+            vertical_stem: vertical.map(|w| WidthDistribution::new(vec![w])),
+            horizontal_bar: horizontal.map(|w| WidthDistribution::new(vec![w])),
+            diagonal: None,
+        }
+    }
+
+    #[test]
+    fn agreeing_stems() {
+        let hints = HintedStems {
+            std_vw: Some(88.0),
+            std_hw: Some(30.0),
+            // Only the closest snap need agree
+            stem_snap_v: vec![40.0, 84.0, 120.0],
+            stem_snap_h: vec![28.0],
+            // Never compared
+            cvt: vec![500.0],
+        };
+        // Within 20%
+        assert!(hints.compare(&stems(Some(80.0), Some(26.0))).is_empty());
+    }
+
+    #[test]
+    fn disagreeing_std_vw() {
+        let hints = HintedStems {
+            std_vw: Some(120.0),
+            stem_snap_v: vec![60.0, 200.0],
+            ..Default::default()
+        };
+        assert_eq!(
+            vec![
+                HintDisagreement {
+                    source: HintSource::StdVW,
+                    hinted: 120.0,
+                    measured: 80.0,
+                },
+                HintDisagreement {
+                    source: HintSource::StemSnapV,
+                    hinted: 60.0,
+                    measured: 80.0,
+                },
+            ],
+            hints.compare(&stems(Some(80.0), Some(26.0)))
+        );
+    }
+
+    #[test]
+    fn horizontal_hints_compare_with_bars() {
+        let hints = HintedStems {
+            std_hw: Some(80.0),
+            stem_snap_h: vec![80.0],
+            ..Default::default()
+        };
+        // Would agree with the vertical stem, doesn't with the bar
+        assert_eq!(
+            vec![HintSource::StdHW, HintSource::StemSnapH],
+            hints
+                .compare(&stems(Some(80.0), Some(26.0)))
+                .iter()
+                .map(|d| d.source)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn nothing_to_compare() {
+        let hints = HintedStems {
+            std_vw: Some(120.0),
+            std_hw: Some(80.0),
+            ..Default::default()
+        };
+        assert!(hints.compare(&stems(None, None)).is_empty());
+        assert!(HintedStems::default().is_empty());
+        assert!(
+            HintedStems::default()
+                .compare(&stems(Some(80.0), Some(26.0)))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn cvt_nearest_each_stem() {
+        let hints = HintedStems {
+            cvt: vec![20.0, 500.0, 84.0, 700.0, 30.0],
+            ..Default::default()
+        };
+        assert_eq!(
+            vec![
+                NearestCvt {
+                    stem: "vertical stem",
+                    cvt: 84.0,
+                    measured: 80.0,
+                },
+                NearestCvt {
+                    stem: "horizontal bar",
+                    cvt: 30.0,
+                    measured: 26.0,
+                },
+            ],
+            hints.nearest_cvt(&stems(Some(80.0), Some(26.0)))
+        );
+        assert!(hints.nearest_cvt(&stems(None, None)).is_empty());
+        assert!(
+            HintedStems::default()
+                .nearest_cvt(&stems(Some(80.0), Some(26.0)))
+                .is_empty()
+        );
+    }
+}
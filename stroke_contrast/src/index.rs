@@ -0,0 +1,432 @@
+//! A bounding volume hierarchy over the segments of a path.
+//!
+//! Ray casting asks the same few questions of a path over and over: where does this line cross it,
+//! what is the winding number here, what is the nearest point on it. Answering by testing every segment
+//! goes quadratic on complex glyphs so we instead only test segments whose bounding box could matter.
+
+use kurbo::{
+    BezPath, Line, LineIntersection, ParamCurve, ParamCurveExtrema, ParamCurveNearest, PathEl,
+    PathSeg, Point, Rect, Shape,
+};
+
+/// Segments per leaf node
+const LEAF_SIZE: usize = 4;
+
+/// Relative distance we move a point off the level of a vertex to count winding reliably
+const VERTEX_NUDGE: f64 = 1e-9;
+
+#[derive(Debug, Clone)]
+enum Node {
+    Leaf {
+        bbox: Rect,
+        start: usize,
+        end: usize,
+    },
+    Branch {
+        bbox: Rect,
+        left: usize,
+        right: usize,
+    },
+}
+
+impl Node {
+    fn bbox(&self) -> Rect {
+        match self {
+            Node::Leaf { bbox, .. } | Node::Branch { bbox, .. } => *bbox,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SegmentIndex {
+    /// (position in path, segment, bbox of segment), reordered so each leaf is a contiguous range
+    segments: Vec<(usize, PathSeg, Rect)>,
+    /// The root, if any, is the last node
+    nodes: Vec<Node>,
+}
+
+impl SegmentIndex {
+    pub fn new(path: &BezPath) -> Self {
+        Self::from_segments(path.segments())
+    }
+
+    /// Index an arbitrary collection of segments, e.g. part of a path
+    pub fn from_segments(segments: impl IntoIterator<Item = PathSeg>) -> Self {
+        let mut segments = segments
+            .into_iter()
+            .enumerate()
+            .map(|(i, s)| (i, s, ParamCurveExtrema::bounding_box(&s)))
+            .collect::<Vec<_>>();
+        let mut nodes = Vec::new();
+        if !segments.is_empty() {
+            let len = segments.len();
+            build(&mut nodes, &mut segments, 0, len);
+        }
+        Self { segments, nodes }
+    }
+
+    /// The number of segments indexed
+    pub fn len(&self) -> usize {
+        self.segments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// The segments indexed, in the order given
+    pub fn segments(&self) -> Vec<PathSeg> {
+        let mut segments = self.segments.clone();
+        segments.sort_by_key(|(i, ..)| *i);
+        segments.into_iter().map(|(_, seg, _)| seg).collect()
+    }
+
+    /// Same result as [BezPath::winding] for the indexed path, except where pt is level with the
+    /// end of a segment
+    pub fn winding(&self, pt: Point) -> i32 {
+        let (winding, through_vertex) = self.winding_and_vertex(pt);
+        if !through_vertex {
+            return winding;
+        }
+        // A ray through a vertex can count the segments either side of it both or neither time,
+        // e.g. at the leftmost point of a bowl. Ask just above instead, it's the same answer for
+        // anything but a point on the path.
+        let pt = Point::new(pt.x, pt.y - VERTEX_NUDGE * pt.y.abs().max(1.0));
+        self.winding_and_vertex(pt).0
+    }
+
+    /// Winding number around pt and whether the ray it casts passes through a segment end
+    fn winding_and_vertex(&self, pt: Point) -> (i32, bool) {
+        // Winding casts a ray to the left so only segments that span pt.y and start left of pt matter
+        let mut winding = 0;
+        let mut through_vertex = false;
+        self.visit(
+            |bbox| bbox.min_y() <= pt.y && pt.y <= bbox.max_y() && bbox.min_x() <= pt.x,
+            |seg| {
+                winding += segment_winding(seg, pt);
+                through_vertex |= seg.start().y == pt.y || seg.end().y == pt.y;
+            },
+        );
+        (winding, through_vertex)
+    }
+
+    /// Every intersection of line with the path, along with the segment intersected, in path order
+    pub fn intersect_line(&self, line: Line) -> Vec<(LineIntersection, PathSeg)> {
+        let mut hits = Vec::new();
+        self.visit_indexed(
+            |bbox| line_may_hit(line, bbox),
+            |i, seg| {
+                hits.extend(
+                    seg.intersect_line(line)
+                        .into_iter()
+                        .map(|isct| (i, isct, seg)),
+                );
+            },
+        );
+        hits.sort_by_key(|(i, ..)| *i);
+        hits.into_iter().map(|(_, isct, seg)| (isct, seg)).collect()
+    }
+
+    /// The nearest point on the path to pt, None if the path is empty
+    pub fn nearest(&self, pt: Point, accuracy: f64) -> Option<Point> {
+        let root = self.nodes.len().checked_sub(1)?;
+        let mut best: Option<(f64, Point)> = None;
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            if let Some((best_dist_sq, _)) = best
+                && distance_sq(node.bbox(), pt) > best_dist_sq
+            {
+                continue;
+            }
+            match node {
+                Node::Leaf { start, end, .. } => {
+                    for (_, seg, bbox) in &self.segments[*start..*end] {
+                        if let Some((best_dist_sq, _)) = best
+                            && distance_sq(*bbox, pt) > best_dist_sq
+                        {
+                            continue;
+                        }
+                        let nearest = seg.nearest(pt, accuracy);
+                        if best.is_none_or(|(best_dist_sq, _)| nearest.distance_sq < best_dist_sq) {
+                            best = Some((nearest.distance_sq, seg.eval(nearest.t)));
+                        }
+                    }
+                }
+                Node::Branch { left, right, .. } => {
+                    // Visit the closer child first, it's more likely to tighten best
+                    let (near, far) = if distance_sq(self.nodes[*left].bbox(), pt)
+                        <= distance_sq(self.nodes[*right].bbox(), pt)
+                    {
+                        (*left, *right)
+                    } else {
+                        (*right, *left)
+                    };
+                    stack.push(far);
+                    stack.push(near);
+                }
+            }
+        }
+        best.map(|(_, pt)| pt)
+    }
+
+    fn visit(&self, may_matter: impl Fn(&Rect) -> bool, mut f: impl FnMut(PathSeg)) {
+        self.visit_indexed(may_matter, |_, seg| f(seg));
+    }
+
+    /// Call f with (position in path, segment) for every segment whose bbox, and whose ancestors
+    /// bboxes, may_matter
+    fn visit_indexed(&self, may_matter: impl Fn(&Rect) -> bool, mut f: impl FnMut(usize, PathSeg)) {
+        let Some(root) = self.nodes.len().checked_sub(1) else {
+            return;
+        };
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            if !may_matter(&node.bbox()) {
+                continue;
+            }
+            match node {
+                Node::Leaf { start, end, .. } => {
+                    for (i, seg, bbox) in &self.segments[*start..*end] {
+                        if may_matter(bbox) {
+                            f(*i, *seg);
+                        }
+                    }
+                }
+                Node::Branch { left, right, .. } => {
+                    stack.push(*right);
+                    stack.push(*left);
+                }
+            }
+        }
+    }
+}
+
+/// Recursively split segments[start..end] at the median along the longer axis of their bboxes centers
+///
+/// Returns the index of the node created.
+fn build(
+    nodes: &mut Vec<Node>,
+    segments: &mut [(usize, PathSeg, Rect)],
+    start: usize,
+    end: usize,
+) -> usize {
+    let bbox = segments[start..end]
+        .iter()
+        .map(|(_, _, bbox)| *bbox)
+        .reduce(|acc, e| acc.union(e))
+        .expect("Non-empty range");
+    if end - start <= LEAF_SIZE {
+        nodes.push(Node::Leaf { bbox, start, end });
+        return nodes.len() - 1;
+    }
+
+    let centers = segments[start..end]
+        .iter()
+        .map(|(_, _, bbox)| Rect::from_points(bbox.center(), bbox.center()))
+        .reduce(|acc, e| acc.union(e))
+        .expect("Non-empty range");
+    let mid = start + (end - start) / 2;
+    if centers.width() >= centers.height() {
+        segments[start..end].select_nth_unstable_by(mid - start, |a, b| {
+            a.2.center().x.total_cmp(&b.2.center().x)
+        });
+    } else {
+        segments[start..end].select_nth_unstable_by(mid - start, |a, b| {
+            a.2.center().y.total_cmp(&b.2.center().y)
+        });
+    }
+    let left = build(nodes, segments, start, mid);
+    let right = build(nodes, segments, mid, end);
+    nodes.push(Node::Branch { bbox, left, right });
+    nodes.len() - 1
+}
+
+/// The winding contribution of a single segment.
+///
+/// [PathSeg] as a [Shape] is always winding 0, a slice of path elements does the per-segment
+/// computation [BezPath::winding] sums.
+fn segment_winding(seg: PathSeg, pt: Point) -> i32 {
+    let elements = [PathEl::MoveTo(seg.start()), seg.as_path_el()];
+    elements.as_slice().winding(pt)
+}
+
+/// Squared distance from pt to the nearest point of bbox, 0 if inside
+fn distance_sq(bbox: Rect, pt: Point) -> f64 {
+    let dx = (bbox.min_x() - pt.x).max(pt.x - bbox.max_x()).max(0.0);
+    let dy = (bbox.min_y() - pt.y).max(pt.y - bbox.max_y()).max(0.0);
+    dx * dx + dy * dy
+}
+
+/// Conservative slab test of line against bbox
+fn line_may_hit(line: Line, bbox: &Rect) -> bool {
+    // Segment intersection is inclusive of points near segment ends, be generous to match
+    let bbox = bbox.inflate(1e-6, 1e-6);
+    let d = line.p1 - line.p0;
+    let mut t0: f64 = 0.0;
+    let mut t1: f64 = 1.0;
+    for (start, delta, min, max) in [
+        (line.p0.x, d.x, bbox.min_x(), bbox.max_x()),
+        (line.p0.y, d.y, bbox.min_y(), bbox.max_y()),
+    ] {
+        if delta == 0.0 {
+            if start < min || start > max {
+                return false;
+            }
+            continue;
+        }
+        let (a, b) = ((min - start) / delta, (max - start) / delta);
+        t0 = t0.max(a.min(b));
+        t1 = t1.min(a.max(b));
+        if t0 > t1 {
+            return false;
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::{
+        Affine, BezPath, Line, LineIntersection, ParamCurve, ParamCurveNearest, PathSeg, Point,
+        Shape,
+    };
+
+    use super::{LEAF_SIZE, SegmentIndex};
+    use crate::test_util::{query_points, wobbly_o};
+
+    /// [LineIntersection] isn't PartialEq
+    fn comparable(
+        hits: impl IntoIterator<Item = (LineIntersection, PathSeg)>,
+    ) -> Vec<(f64, f64, PathSeg)> {
+        hits.into_iter()
+            .map(|(isct, seg)| (isct.line_t, isct.segment_t, seg))
+            .collect()
+    }
+
+    fn brute_force_nearest_dist(path: &BezPath, pt: Point) -> f64 {
+        path.segments()
+            .map(|s| s.nearest(pt, 1e-9).distance_sq)
+            .min_by(f64::total_cmp)
+            .unwrap()
+            .sqrt()
+    }
+
+    #[test]
+    fn winding_matches_brute_force() {
+        let path = wobbly_o(200);
+        let index = SegmentIndex::new(&path);
+        for pt in query_points(500) {
+            assert_eq!(path.winding(pt), index.winding(pt), "{pt:?}");
+        }
+    }
+
+    #[test]
+    fn winding_through_a_vertex() {
+        // The ray cast left from pt passes exactly through the vertex at (10, 30), summing the
+        // winding of each segment then counts the contour once too often
+        let path = BezPath::from_svg("M10,30 Q50,40 40,80 Q70,30 90,80 Q100,40 10,30 Z").unwrap();
+        let pt = Point::new(65.0, 30.0);
+        assert_ne!(0, path.winding(pt));
+        let index = SegmentIndex::new(&path);
+        assert_eq!(0, index.winding(pt));
+        assert_eq!(path.winding(pt - (0.0, 0.01)), index.winding(pt));
+        assert_eq!(path.winding(pt + (0.0, 0.01)), index.winding(pt));
+    }
+
+    #[test]
+    fn intersect_line_matches_brute_force() {
+        let path = wobbly_o(200);
+        let index = SegmentIndex::new(&path);
+        for (i, pt) in query_points(500).into_iter().enumerate() {
+            let ray = Affine::rotate_about(i as f64, pt)
+                * Line::new(pt - (2000.0, 0.0), pt + (2000.0, 0.0));
+            let brute = comparable(
+                path.segments()
+                    .flat_map(|s| s.intersect_line(ray).into_iter().map(move |i| (i, s))),
+            );
+            assert_eq!(brute, comparable(index.intersect_line(ray)), "{ray:?}");
+        }
+    }
+
+    #[test]
+    fn intersect_line_through_a_vertex() {
+        // A square of 4 segments per side so the corners fall inside and between leaves
+        let mut path = BezPath::new();
+        path.move_to((0.0, 0.0));
+        for corner in [(100.0, 0.0), (100.0, 100.0), (0.0, 100.0), (0.0, 0.0)] {
+            let start = path.segments().last().map(|s| s.end()).unwrap_or_default();
+            for i in 1..=4 {
+                path.line_to(start.lerp(corner.into(), i as f64 / 4.0));
+            }
+        }
+        path.close_path();
+        let index = SegmentIndex::new(&path);
+        assert!(index.len() > LEAF_SIZE);
+
+        // The diagonal passes through two corners, a horizontal through vertices on both sides
+        for ray in [
+            Line::new((-10.0, -10.0), (110.0, 110.0)),
+            Line::new((-10.0, 50.0), (110.0, 50.0)),
+            Line::new((-10.0, 25.0), (110.0, 25.0)),
+        ] {
+            let brute = comparable(
+                path.segments()
+                    .flat_map(|s| s.intersect_line(ray).into_iter().map(move |i| (i, s))),
+            );
+            assert!(brute.len() >= 2, "{ray:?} {brute:?}");
+            assert_eq!(brute, comparable(index.intersect_line(ray)), "{ray:?}");
+        }
+    }
+
+    #[test]
+    fn nearest_matches_brute_force() {
+        let path = wobbly_o(200);
+        let index = SegmentIndex::new(&path);
+        for pt in query_points(500) {
+            let nearest = index.nearest(pt, 1e-9).unwrap();
+            let brute = brute_force_nearest_dist(&path, pt);
+            assert!(
+                (brute - (nearest - pt).length()).abs() < 1e-6,
+                "{pt:?} {nearest:?} {brute}"
+            );
+        }
+    }
+
+    #[test]
+    fn nearest_across_leaf_boundaries() {
+        let path = wobbly_o(64);
+        let index = SegmentIndex::new(&path);
+        // Segments are split into leaves in runs along the ring so the ends of every segment, and
+        // points just off them, exercise the boundaries between leaves
+        for seg in path.segments() {
+            for pt in [
+                seg.start(),
+                seg.end(),
+                seg.eval(0.999),
+                seg.end() + (3.0, -2.0),
+            ] {
+                let nearest = index.nearest(pt, 1e-9).unwrap();
+                let brute = brute_force_nearest_dist(&path, pt);
+                assert!(
+                    (brute - (nearest - pt).length()).abs() < 1e-6,
+                    "{pt:?} {nearest:?} {brute}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn empty() {
+        let index = SegmentIndex::new(&BezPath::new());
+        assert!(index.is_empty());
+        assert_eq!(0, index.winding(Point::ORIGIN));
+        assert_eq!(None, index.nearest(Point::ORIGIN, 1e-9));
+        assert!(
+            index
+                .intersect_line(Line::new((-1.0, 0.0), (1.0, 0.0)))
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,221 @@
+//! Estimate stroke widths between the locations we actually measured.
+//!
+//! Ray casting is slow, multilinear interpolation across the sampled grid is not, so downstream
+//! tools can ask about any location once a font has been measured at a few.
+
+use fontdrasil::coords::UserLocation;
+use skrifa::{AxisCollection, MetadataProvider, Tag};
+
+/// Estimated widths at a location, in the same units as the measurements interpolated
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WidthEstimate {
+    pub min_width: f64,
+    pub max_width: f64,
+}
+
+/// Interpolates in normalized design space, after avar, as the font itself varies
+#[derive(Clone)]
+pub struct WidthInterpolator<'a> {
+    axes: AxisCollection<'a>,
+    grid: Grid,
+}
+
+impl<'a> WidthInterpolator<'a> {
+    /// measurements are (location, min width, max width), e.g. as collected while measuring
+    /// each of [crate::locations_of_interest]. Axes a location omits are at default and
+    /// measurements at axes the font doesn't have are ignored.
+    pub fn new(font: &skrifa::FontRef<'a>, measurements: &[(UserLocation, f64, f64)]) -> Self {
+        let axes = font.axes();
+        let samples = measurements
+            .iter()
+            .filter_map(|(loc, min_width, max_width)| {
+                Some((normalize(&axes, loc)?, *min_width, *max_width))
+            })
+            .collect();
+        let grid = Grid::new(axes.len(), samples);
+        Self { axes, grid }
+    }
+
+    /// Estimate widths at loc, axes it omits are at default.
+    ///
+    /// Interpolates between the sampled values bracketing loc on each axis that was sampled at
+    /// more than one value and clamps to the sampled range, we don't extrapolate. If the samples
+    /// don't form a complete grid the corners that were measured are reweighted. None if none
+    /// were, nothing was measured or loc names an axis the font doesn't have.
+    pub fn estimate(&self, loc: &UserLocation) -> Option<WidthEstimate> {
+        self.grid.estimate(&normalize(&self.axes, loc)?)
+    }
+}
+
+/// Normalized coordinates of loc, in font axis order, with omitted axes at default.
+///
+/// None if loc has an axis the font doesn't, we can't say anything about it.
+fn normalize(axes: &AxisCollection, loc: &UserLocation) -> Option<Vec<f64>> {
+    let settings = settings(&axes.iter().map(|a| a.tag()).collect::<Vec<_>>(), loc)?;
+    Some(
+        axes.location(settings)
+            .coords()
+            .iter()
+            .map(|c| c.to_f32() as f64)
+            .collect(),
+    )
+}
+
+/// The user coordinates of loc as variation settings, None if it has an axis not among tags
+fn settings(tags: &[Tag], loc: &UserLocation) -> Option<Vec<(Tag, f32)>> {
+    if loc.axis_tags().any(|tag| !tags.contains(tag)) {
+        return None;
+    }
+    Some(
+        tags.iter()
+            .filter_map(|tag| loc.get(*tag).map(|c| (*tag, c.to_f64() as f32)))
+            .collect(),
+    )
+}
+
+/// Measurements at full coordinates, one per axis
+#[derive(Debug, Clone)]
+struct Grid {
+    /// Distinct sampled values per axis, ascending
+    values: Vec<Vec<f64>>,
+    /// (coordinates, min width, max width)
+    samples: Vec<(Vec<f64>, f64, f64)>,
+}
+
+impl Grid {
+    fn new(axis_count: usize, samples: Vec<(Vec<f64>, f64, f64)>) -> Self {
+        let values = (0..axis_count)
+            .map(|i| {
+                let mut values = samples.iter().map(|(c, ..)| c[i]).collect::<Vec<_>>();
+                values.sort_by(f64::total_cmp);
+                values.dedup_by(|a, b| (*a - *b).abs() < 1e-6);
+                values
+            })
+            .collect();
+        Self { values, samples }
+    }
+
+    /// Estimate widths at query, see [WidthInterpolator::estimate]
+    fn estimate(&self, query: &[f64]) -> Option<WidthEstimate> {
+        // Per axis, the (value, weight) of each bracketing sample value
+        let brackets = self
+            .values
+            .iter()
+            .zip(query.iter())
+            .map(|(values, q)| bracket(values, *q))
+            .collect::<Option<Vec<_>>>()?;
+
+        let (mut total_weight, mut min_width, mut max_width) = (0.0, 0.0, 0.0);
+        let corners = brackets.iter().map(Vec::len).product::<usize>();
+        for corner in 0..corners {
+            let mut remainder = corner;
+            let mut weight = 1.0;
+            let mut corner_coords = Vec::with_capacity(brackets.len());
+            for bracket in brackets.iter() {
+                let (value, w) = bracket[remainder % bracket.len()];
+                remainder /= bracket.len();
+                weight *= w;
+                corner_coords.push(value);
+            }
+            if weight == 0.0 {
+                continue;
+            }
+            let Some((_, min, max)) = self.samples.iter().find(|(c, ..)| {
+                c.iter()
+                    .zip(corner_coords.iter())
+                    .all(|(a, b)| (a - b).abs() < 1e-6)
+            }) else {
+                continue;
+            };
+            total_weight += weight;
+            min_width += weight * min;
+            max_width += weight * max;
+        }
+        (total_weight > 0.0).then(|| WidthEstimate {
+            min_width: min_width / total_weight,
+            max_width: max_width / total_weight,
+        })
+    }
+}
+
+/// The sampled values either side of q and their linear weights, clamped to the sampled range
+fn bracket(values: &[f64], q: f64) -> Option<Vec<(f64, f64)>> {
+    let (first, last) = (*values.first()?, *values.last()?);
+    if q <= first {
+        return Some(vec![(first, 1.0)]);
+    }
+    if q >= last {
+        return Some(vec![(last, 1.0)]);
+    }
+    let hi = values.partition_point(|v| *v < q);
+    let (lo, hi) = (values[hi - 1], values[hi]);
+    let t = (q - lo) / (hi - lo);
+    Some(vec![(lo, 1.0 - t), (hi, t)])
+}
+
+#[cfg(test)]
+mod tests {
+    use fontdrasil::coords::UserLocation;
+
+    use super::{Grid, WidthEstimate, bracket, settings};
+    use crate::test_util::{WDTH, WGHT, loc};
+
+    #[test]
+    fn bracket_weights_neighbours() {
+        let values = [100.0, 400.0, 900.0];
+        assert_eq!(
+            Some(vec![(400.0, 0.75), (900.0, 0.25)]),
+            bracket(&values, 525.0)
+        );
+        assert_eq!(Some(vec![(400.0, 1.0)]), bracket(&values[1..2], 525.0));
+        assert_eq!(Some(vec![(100.0, 1.0)]), bracket(&values, 50.0));
+        assert_eq!(Some(vec![(900.0, 1.0)]), bracket(&values, 1000.0));
+        assert_eq!(None, bracket(&[], 400.0));
+    }
+
+    #[test]
+    fn estimate_interpolates_and_clamps() {
+        let grid = Grid::new(1, vec![(vec![-1.0], 10.0, 20.0), (vec![0.0], 40.0, 80.0)]);
+        assert_eq!(
+            Some(WidthEstimate {
+                min_width: 25.0,
+                max_width: 50.0
+            }),
+            grid.estimate(&[-0.5])
+        );
+        // We don't extrapolate
+        assert_eq!(grid.estimate(&[0.0]), grid.estimate(&[1.0]));
+    }
+
+    #[test]
+    fn estimate_reweights_missing_corners() {
+        // Three corners of a wght x wdth square, the fourth (1, 1) missing
+        let grid = Grid::new(
+            2,
+            vec![
+                (vec![0.0, 0.0], 10.0, 10.0),
+                (vec![1.0, 0.0], 30.0, 30.0),
+                (vec![0.0, 1.0], 20.0, 20.0),
+            ],
+        );
+        // Each corner present is weighted 1/4 of the 3/4 total
+        let estimate = grid.estimate(&[0.5, 0.5]).unwrap();
+        assert!((estimate.min_width - 20.0).abs() < 1e-9, "{estimate:?}");
+    }
+
+    #[test]
+    fn nothing_measured_estimates_nothing() {
+        assert_eq!(None, Grid::new(1, Vec::new()).estimate(&[0.0]));
+    }
+
+    #[test]
+    fn settings_of_known_axes() {
+        assert_eq!(
+            Some(vec![(WGHT, 300.0)]),
+            settings(&[WGHT, WDTH], &loc(&[(WGHT, 300.0)]))
+        );
+        // Omitted axes are left to default
+        assert_eq!(Some(Vec::new()), settings(&[WGHT], &UserLocation::new()));
+        assert_eq!(None, settings(&[WGHT], &loc(&[(WDTH, 80.0)])));
+    }
+}
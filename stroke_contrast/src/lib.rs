@@ -1,19 +1,63 @@
-use fontdrasil::coords::{UserCoord, UserLocation};
+use fontdrasil::coords::UserLocation;
 use harfruzz::{GlyphBuffer, ShaperFont};
 use kurbo::{
-    Affine, BezPath, Circle, Line, ParamCurve, ParamCurveNearest, PathSeg, Point, Rect, Shape, Vec2,
+    Affine, BezPath, Circle, Line, LineIntersection, ParamCurve, ParamCurveDeriv,
+    ParamCurveNearest, PathSeg, Point, Rect, Shape, Vec2, common::GAUSS_LEGENDRE_COEFFS_5,
 };
-use log::{debug, warn};
+use log::debug;
 use ordered_float::OrderedFloat;
 use read_fonts::types::F2Dot14;
 use skrifa::{
-    MetadataProvider, Tag,
+    GlyphId, MetadataProvider,
     instance::Location,
-    outline::{DrawSettings, OutlinePen},
+    outline::{DrawSettings, OutlineGlyphCollection, OutlinePen},
     prelude::{LocationRef, Size},
     raw::TableProvider,
 };
 
+mod budget;
+mod contours;
+mod contrast;
+mod curve;
+mod distribution;
+mod error;
+mod glyph_set;
+mod hinting;
+mod index;
+mod interpolate;
+mod locations;
+mod options;
+mod orientation;
+mod script;
+mod seeds;
+mod shaping;
+mod stress;
+#[cfg(test)]
+mod test_util;
+
+pub use budget::Budget;
+use budget::{MAX_PARTIAL_RIBS, Spent, Work, check_ray_count};
+pub use contours::{ContourReport, FillRule};
+pub use contrast::{Contrast, ContrastClass};
+pub use curve::{CurveAnomaly, CurveMetric, CurvePoint, WidthCurve};
+pub use distribution::{Histogram, WidthDistribution};
+pub use error::StrokeContrastError;
+pub use glyph_set::{
+    GlyphMeasurement, GlyphSelector, GlyphSetMeasurement, measure_glyph_set, parse_glyph_set,
+};
+pub use hinting::{HintDisagreement, HintSource, HintedStems, NearestCvt};
+pub use index::SegmentIndex;
+pub use interpolate::{WidthEstimate, WidthInterpolator};
+pub use locations::{
+    LocationOptions, NamedLocation, Sampling, instance_name, is_default_location,
+    locations_of_interest, named_instances,
+};
+pub use options::{CircleFit, MeasureOptions};
+pub use orientation::{RibOrientation, StemWidths};
+pub use script::{MEASUREMENT_CHARS, measurement_char, script_measurement_chars};
+pub use shaping::ShapingOptions;
+pub use stress::{DEFAULT_MIN_STRESS_CONFIDENCE, Stress, stress_angle};
+
 pub fn csv_fragment(user: &UserLocation) -> String {
     if user.iter().next().is_none() {
         return String::from("");
@@ -36,31 +80,45 @@ pub fn csv_fragment(user: &UserLocation) -> String {
     maybe_quote.to_string() + &tags + "@" + &values + maybe_quote
 }
 
-pub fn locations_of_interest(font: &skrifa::FontRef) -> Vec<UserLocation> {
-    const WGHT_TAG: Tag = Tag::new(b"wght");
-    let result = vec![UserLocation::new()];
-
-    let Ok(fvar) = font.fvar() else {
-        return result;
-    };
-    let Some(wght_axis) = fvar
-        .axes()
-        .unwrap()
+/// Green's theorem integrals over a segment: (∮x dy, ∮x²/2 dy, ∮y²/2 dx)
+///
+/// The integrands are at most degree 8 for a cubic so 5 point Gauss-Legendre is exact.
+fn area_moments<C: ParamCurve + ParamCurveDeriv>(curve: &C) -> (f64, f64, f64) {
+    let deriv = curve.deriv();
+    GAUSS_LEGENDRE_COEFFS_5
         .iter()
-        .find(|a| a.axis_tag() == WGHT_TAG)
-    else {
-        return result;
-    };
+        .fold((0.0, 0.0, 0.0), |acc, (weight, x)| {
+            // Map [-1, 1] to [0, 1], hence the half weight
+            let t = 0.5 * (x + 1.0);
+            let weight = 0.5 * weight;
+            let pt = curve.eval(t);
+            let d = deriv.eval(t).to_vec2();
+            (
+                acc.0 + weight * pt.x * d.y,
+                acc.1 + weight * 0.5 * pt.x * pt.x * d.y,
+                acc.2 + weight * 0.5 * pt.y * pt.y * d.x,
+            )
+        })
+}
 
-    let mut result = Vec::new();
-    for wght in
-        (wght_axis.min_value.get().to_i32()..=wght_axis.max_value.get().to_i32()).step_by(100)
-    {
-        let mut user = UserLocation::new();
-        user.insert(WGHT_TAG, UserCoord::new(wght));
-        result.push(user);
+/// The area centroid of a path, None if the path encloses no area
+///
+/// Computed analytically from the segments, overlapping regions count once per contour that covers them.
+pub fn centroid(path: &BezPath) -> Option<Point> {
+    let (area, moment_x, moment_y) = path
+        .segments()
+        .map(|seg| match seg {
+            PathSeg::Line(line) => area_moments(&line),
+            PathSeg::Quad(quad) => area_moments(&quad),
+            PathSeg::Cubic(cubic) => area_moments(&cubic),
+        })
+        .fold((0.0, 0.0, 0.0), |acc, e| {
+            (acc.0 + e.0, acc.1 + e.1, acc.2 + e.2)
+        });
+    if area.abs() < 1e-9 {
+        return None;
     }
-    result
+    Some(Point::new(moment_x / area, -moment_y / area))
 }
 
 trait Tangent {
@@ -82,16 +140,45 @@ impl Tangent for PathSeg {
                 // B'(t) = 2(1-t)(p1-p0)+2t(p2-p1)
                 let curr = quad.eval(t);
                 let tan = 2.0 * (1.0 - t) * (quad.p1 - quad.p0) + 2.0 * t * (quad.p2 - quad.p1);
+                if is_degenerate(tan) {
+                    // Off-curve point sits on an endpoint, the curve heads straight for the other end
+                    return (curr, quad.p2 - quad.p0);
+                }
                 (curr, tan)
             }
-            PathSeg::Cubic(_cubic) => {
+            PathSeg::Cubic(cubic) => {
                 // B'(t) = 3(1-t)^2(p1-p0) + 6(1-t)t(p2 - p1) + 3 * t^2 * (p3 - p2)
-                todo!("Implement cubic per comment above")
+                let curr = cubic.eval(t);
+                let tan = 3.0 * (1.0 - t).powi(2) * (cubic.p1 - cubic.p0)
+                    + 6.0 * (1.0 - t) * t * (cubic.p2 - cubic.p1)
+                    + 3.0 * t.powi(2) * (cubic.p3 - cubic.p2);
+                if !is_degenerate(tan) {
+                    return (curr, tan);
+                }
+
+                // B'(t) vanishes, typically because a control point coincides with an endpoint.
+                // Near such a t B'(t) ~= (t - t0) B''(t0) so the limiting direction is B''
+                // leaving the start and -B'' arriving at the end.
+                // B''(t) = 6(1-t)(p2 - 2p1 + p0) + 6t(p3 - 2p2 + p1)
+                let second = 6.0 * (1.0 - t) * ((cubic.p2 - cubic.p1) - (cubic.p1 - cubic.p0))
+                    + 6.0 * t * ((cubic.p3 - cubic.p2) - (cubic.p2 - cubic.p1));
+                let second = if t < 0.5 { second } else { -second };
+                if !is_degenerate(second) {
+                    return (curr, second);
+                }
+
+                // Both control points sit on one end, the curve is effectively a line
+                (curr, cubic.p3 - cubic.p0)
             }
         }
     }
 }
 
+/// True if a derivative is too small to give a meaningful direction
+fn is_degenerate(v: Vec2) -> bool {
+    v.hypot2() < 1e-12
+}
+
 struct PathPen {
     transform: Affine,
     path: BezPath,
@@ -170,7 +257,12 @@ impl OutlinePen for PathPen {
 
 // Simplified version of <https://github.com/harfbuzz/harfruzz/blob/006472176ab87e3a84e799e74e0ac19fbe943dd7/tests/shaping/main.rs#L107>
 // Will have to update if/when that API updates
-fn shape(text: &str, font: &harfruzz::FontRef, loc: &LocationRef) -> GlyphBuffer {
+fn shape(
+    text: &str,
+    font: &harfruzz::FontRef,
+    loc: &LocationRef,
+    shaping: &ShapingOptions,
+) -> GlyphBuffer {
     let coords = loc
         .coords()
         .iter()
@@ -179,10 +271,55 @@ fn shape(text: &str, font: &harfruzz::FontRef, loc: &LocationRef) -> GlyphBuffer
     let shaper_font = ShaperFont::new(font);
     let face = shaper_font.shaper(font, &coords);
 
-    let mut buffer = harfruzz::UnicodeBuffer::new();
-    buffer.push_str(text);
+    harfruzz::shape(&face, &shaping.features, shaping.buffer(text))
+}
+
+/// Where to draw each glyph of a shaped run given its (offset, advance), in font units.
+///
+/// The pen position is tracked y-up, as shaping reports it, and flipped only once we're placing
+/// the glyph: each glyph is drawn at the pen plus its offset, then the pen moves by its advance.
+fn glyph_transforms(positions: impl IntoIterator<Item = (Vec2, Vec2)>) -> Vec<Affine> {
+    let mut origin = Vec2::ZERO;
+    positions
+        .into_iter()
+        .map(|(offset, advance)| {
+            let transform = Affine::FLIP_Y * Affine::translate(origin + offset);
+            origin += advance;
+            transform
+        })
+        .collect()
+}
+
+/// Draw gid at loc into pen, in font units
+fn draw(
+    outlines: &OutlineGlyphCollection,
+    gid: GlyphId,
+    loc: &Location,
+    pen: &mut PathPen,
+) -> Result<(), StrokeContrastError> {
+    let glyph = outlines
+        .get(gid)
+        .ok_or(StrokeContrastError::MissingGlyph(gid))?;
+    glyph
+        .draw(
+            DrawSettings::unhinted(Size::unscaled(), LocationRef::from(loc)),
+            pen,
+        )
+        .map_err(|e| StrokeContrastError::DrawFailure(gid, e))?;
+    Ok(())
+}
 
-    harfruzz::shape(&face, &[], buffer)
+/// How to discover strokes
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+pub enum SegmentSelection {
+    /// Cast rays from center of mass, or from each counter if that's inked, stopping at nearest
+    /// path segment
+    #[default]
+    CenterOfMass,
+    /// Cast multiple rays perpendicular to each path segment
+    AllSegments,
+    /// Fit maximal inscribed circles along an approximate medial axis of the outline
+    MedialAxis,
 }
 
 pub struct WidthReader {
@@ -190,113 +327,289 @@ pub struct WidthReader {
     pub bbox: Rect,
     pub max_dim: f64,
     pub ray_width: f64,
+    pub options: MeasureOptions,
+    /// What was found, and corrected, in the contours of path
+    pub contours: ContourReport,
+    /// Contours of path that enclose counters, each indexed for winding
+    counters: Vec<(BezPath, SegmentIndex)>,
+    /// Every segment of path, for winding
+    index: SegmentIndex,
+    /// Segments that separate ink from no ink, where rays stop
+    edges: SegmentIndex,
+    /// Work done preparing path, charged to every measurement
+    spent: Spent,
 }
 
 impl WidthReader {
-    pub fn new(raw_font: &[u8], ch: char, loc: &Location) -> Self {
-        let harf_font_ref =
-            harfruzz::FontRef::new(&raw_font).expect("For font files to be font files!");
-        let skrifa_font_ref = skrifa::FontRef::new(&raw_font).expect("Fonts to be fonts");
+    pub fn new(
+        raw_font: &[u8],
+        ch: char,
+        loc: &Location,
+        options: MeasureOptions,
+    ) -> Result<Self, StrokeContrastError> {
+        Self::with_shaping(raw_font, ch, &ShapingOptions::default(), loc, options)
+    }
 
+    /// Like [WidthReader::new] but shaping with the given features, script and language
+    pub fn with_shaping(
+        raw_font: &[u8],
+        ch: char,
+        shaping: &ShapingOptions,
+        loc: &Location,
+        options: MeasureOptions,
+    ) -> Result<Self, StrokeContrastError> {
+        Self::from_text(raw_font, &ch.to_string(), shaping, loc, options)
+    }
+
+    /// Shape text and measure the resulting glyphs as a single outline.
+    ///
+    /// Glyphs are placed where shaping says, offsets included, so words and clusters with
+    /// attached marks compose as they would be rendered.
+    pub fn from_text(
+        raw_font: &[u8],
+        text: &str,
+        shaping: &ShapingOptions,
+        loc: &Location,
+        options: MeasureOptions,
+    ) -> Result<Self, StrokeContrastError> {
+        let harf_font_ref = harfruzz::FontRef::new(raw_font)
+            .map_err(|e| StrokeContrastError::FontParse(e.to_string()))?;
+        let skrifa_font_ref = skrifa::FontRef::new(raw_font)
+            .map_err(|e| StrokeContrastError::FontParse(e.to_string()))?;
         let outlines = skrifa_font_ref.outline_glyphs();
         let mut pen = PathPen::default();
 
-        let glyphs = shape(&format!("{}", ch), &harf_font_ref, &LocationRef::from(loc));
-        if glyphs.is_empty() || glyphs.glyph_infos().iter().any(|gi| gi.glyph_id == 0) {
-            panic!("Shaping failed {glyphs:#?}");
+        // Shape first, default ignorables such as ZWJ need not be mapped for shaping to succeed
+        let glyphs = shape(text, &harf_font_ref, &LocationRef::from(loc), shaping);
+        if glyphs.is_empty() {
+            return Err(StrokeContrastError::ShapedToNotdef(text.to_string()));
         }
-        for (glyph_info, pos) in glyphs.glyph_infos().iter().zip(glyphs.glyph_positions()) {
-            let glyph = outlines
-                .get(glyph_info.glyph_id.into())
-                .expect("Glyphs to exist!");
-            glyph
-                .draw(
-                    DrawSettings::unhinted(Size::unscaled(), LocationRef::from(loc)),
-                    &mut pen,
-                )
-                .expect("To draw!");
-
-            pen.transform = pen.transform.then_translate(Vec2 {
-                x: pos.x_advance.into(),
-                y: pos.y_advance.into(),
-            });
+        if let Some(notdef) = glyphs.glyph_infos().iter().find(|gi| gi.glyph_id == 0) {
+            debug!("Shaping failed {glyphs:#?}");
+            // Blame the character that produced .notdef if the font doesn't map it
+            let charmap = skrifa_font_ref.charmap();
+            return Err(
+                match text
+                    .get(notdef.cluster as usize..)
+                    .and_then(|s| s.chars().next())
+                {
+                    Some(ch) if charmap.map(ch).is_none() => {
+                        StrokeContrastError::UnmappedCharacter(ch)
+                    }
+                    _ => StrokeContrastError::ShapedToNotdef(text.to_string()),
+                },
+            );
         }
+        let transforms = glyph_transforms(glyphs.glyph_positions().iter().map(|pos| {
+            (
+                Vec2::new(pos.x_offset.into(), pos.y_offset.into()),
+                Vec2::new(pos.x_advance.into(), pos.y_advance.into()),
+            )
+        }));
+        for (glyph_info, transform) in glyphs.glyph_infos().iter().zip(transforms) {
+            pen.transform = transform;
+            draw(&outlines, GlyphId::new(glyph_info.glyph_id), loc, &mut pen)?;
+        }
+
+        Self::from_path(pen.path, options)
+    }
 
-        let path = pen.path;
+    /// Measure a specific glyph, bypassing cmap and shaping.
+    ///
+    /// Useful for glyphs shaping won't readily produce such as alternates and unencoded components.
+    pub fn from_glyph_id(
+        raw_font: &[u8],
+        gid: GlyphId,
+        loc: &Location,
+        options: MeasureOptions,
+    ) -> Result<Self, StrokeContrastError> {
+        let font = skrifa::FontRef::new(raw_font)
+            .map_err(|e| StrokeContrastError::FontParse(e.to_string()))?;
+        let mut pen = PathPen::default();
+        draw(&font.outline_glyphs(), gid, loc, &mut pen)?;
+        Self::from_path(pen.path, options)
+    }
+
+    /// Measure a glyph by name, e.g. a.sc, as given by the post or CFF table
+    pub fn from_glyph_name(
+        raw_font: &[u8],
+        name: &str,
+        loc: &Location,
+        options: MeasureOptions,
+    ) -> Result<Self, StrokeContrastError> {
+        let font = skrifa::FontRef::new(raw_font)
+            .map_err(|e| StrokeContrastError::FontParse(e.to_string()))?;
+        let gid = font
+            .glyph_names()
+            .iter()
+            .find(|(_, glyph_name)| glyph_name.as_str() == name)
+            .map(|(gid, _)| gid)
+            .ok_or_else(|| StrokeContrastError::UnknownGlyphName(name.to_string()))?;
+        Self::from_glyph_id(raw_font, gid, loc, options)
+    }
+
+    fn from_path(path: BezPath, options: MeasureOptions) -> Result<Self, StrokeContrastError> {
+        if centroid(&path).is_none() {
+            return Err(StrokeContrastError::EmptyOutline);
+        }
         let bbox = path.bounding_box();
         let max_dim = bbox.width().max(bbox.height());
-        let margin = 0.03 * max_dim;
+        let margin = options.bbox_margin * max_dim;
         let bbox = bbox.inflate(margin, margin).expand();
         let ray_width = margin / 64.0;
-        Self {
+
+        // Preparing the outline is charged to every measurement of it
+        let work = Work::new(&options, path.segments().count());
+        let budget_exceeded = |budget| StrokeContrastError::BudgetExceeded {
+            budget,
+            partial: None,
+        };
+        let contours::Normalized {
+            path,
+            counters,
+            report: contours,
+        } = contours::normalize(
+            &path,
+            options.fill_rule,
+            options.reverse_video_threshold * bbox.area(),
+            &work,
+        )
+        .map_err(budget_exceeded)?;
+        let index = SegmentIndex::new(&path);
+        let edges = SegmentIndex::from_segments(
+            contours::boundary_segments(&path, &index, options.fill_rule, ray_width, &work)
+                .map_err(budget_exceeded)?,
+        );
+        let counters = counters
+            .into_iter()
+            .map(|counter| {
+                let index = SegmentIndex::new(&counter);
+                (counter, index)
+            })
+            .collect();
+        Ok(Self {
             path,
             bbox,
             max_dim,
             ray_width,
+            options,
+            contours,
+            counters,
+            index,
+            edges,
+            spent: work.spent(),
+        })
+    }
+
+    pub fn measure(
+        &self,
+        method: SegmentSelection,
+    ) -> Result<WidthCandidates, StrokeContrastError> {
+        match method {
+            SegmentSelection::CenterOfMass => self.cast_rays_around_center_of_mass(),
+            SegmentSelection::AllSegments => self.cast_rays_from_all_segments(),
+            SegmentSelection::MedialAxis => self.fit_circles_along_medial_axis(),
         }
     }
 
-    /// Spray rays from center of mass. Currently baselessly assumes center of mass will be uninked.
-    pub fn cast_rays_around_center_of_mass(&self) -> WidthCandidates {
-        // Brute force discovery of interior pixels and center of mass
-        // TODO: migrate to analytic solution once available in kurbo
-        let bbox = self.bbox;
-        let mut live = Vec::new();
-        let mut num_filled = 0;
-        let mut num_unfilled = 0;
-        for x in (bbox.min_x() as i32..(bbox.min_x() + bbox.width()) as i32)
-            .step_by((bbox.width() / 100.0).floor() as usize)
-        {
-            for y in (bbox.min_y() as i32..(bbox.min_y() + bbox.height()) as i32)
-                .step_by((bbox.height() / 100.0).floor() as usize)
-            {
-                if self.path.winding(Point {
-                    x: x as f64,
-                    y: y as f64,
-                }) != 0
-                {
-                    live.push((x as f64, y as f64));
-                    num_filled += 1;
-                } else {
-                    num_unfilled += 1;
-                }
+    /// Spray rays from center of mass.
+    ///
+    /// If the center of mass is inked, as for an 'e' or an 'a', rays are instead sprayed from
+    /// inside each counter and the results merged. Fails if there are no counters either.
+    pub fn cast_rays_around_center_of_mass(&self) -> Result<WidthCandidates, StrokeContrastError> {
+        let work = self.start_work();
+
+        let center_of_mass = centroid(&self.path).ok_or(StrokeContrastError::EmptyOutline)?;
+        let seeds = if self.is_inked(&work, center_of_mass) {
+            let seeds = self
+                .counter_seeds(&work)
+                .map_err(|budget| self.budget_exceeded(budget, &work, Vec::new(), Vec::new()))?;
+            if seeds.is_empty() {
+                return Err(StrokeContrastError::InkedCentroid(center_of_mass));
+            }
+            debug!(
+                "Center of mass {center_of_mass:?} is inked, spraying from counters at {seeds:?}"
+            );
+            seeds
+        } else {
+            vec![center_of_mass]
+        };
+        // Every seed sprays a full set of rays
+        check_ray_count(&self.options, seeds.len() * self.options.ray_count).map_err(|budget| {
+            StrokeContrastError::BudgetExceeded {
+                budget,
+                partial: None,
+            }
+        })?;
+
+        let mut rays = Vec::new();
+        let mut ribs = Vec::new();
+        for seed in seeds {
+            if let Err(budget) = self.cast_rays_around(&work, seed, &mut rays, &mut ribs) {
+                return Err(self.budget_exceeded(budget, &work, rays, ribs));
             }
         }
 
-        if num_filled as f64 > 0.75 * (num_filled as f64 + num_unfilled as f64) {
-            warn!("OMG reverse video?! TODO: invert winding?");
-        };
+        WidthCandidates::new(self, rays, ribs, &work)
+    }
 
-        let (sum_x, sum_y) = live
-            .iter()
-            .fold((0.0, 0.0), |acc, e| (acc.0 + e.0, acc.1 + e.1));
-        let center_of_mass = Point::new(sum_x / live.len() as f64, sum_y / live.len() as f64);
-        if self.path.winding(center_of_mass) != 0 {
-            panic!("Being filled at center of mass not supported for this method");
+    /// A point well inside each counter to spray rays from.
+    ///
+    /// That's the centroid of the counter if it's clear of ink, otherwise its pole of
+    /// inaccessibility, the point furthest from any edge.
+    fn counter_seeds(&self, work: &Work) -> Result<Vec<Point>, Budget> {
+        let mut seeds = Vec::new();
+        for (counter, index) in self.counters.iter() {
+            let in_counter = |pt: Point| {
+                work.charge_query(index.len());
+                index.winding(pt) != 0 && !self.is_inked(work, pt)
+            };
+            if let Some(center) = centroid(counter)
+                && in_counter(center)
+            {
+                seeds.push(center);
+                continue;
+            }
+            let signed_distance = |pt: Point| -> Result<f64, Budget> {
+                work.check()?;
+                let distance = (pt - self.nearest_on_path(work, pt)).length();
+                Ok(if in_counter(pt) { distance } else { -distance })
+            };
+            if let Some((pole, _)) = seeds::pole_of_inaccessibility(
+                counter.bounding_box(),
+                self.ray_width,
+                signed_distance,
+            )? {
+                seeds.push(pole);
+            }
         }
-        // svg.push_str(&format!("  <circle r=\"{margin}\" "));
-        // svg.push_str(&format!("cx=\"{}\" cy=\"{}\" ", center_of_mass.x, center_of_mass.y));
-        // svg.push_str("fill=\"purple\"");
-        // svg.push_str("/>\n");
+        Ok(seeds)
+    }
 
-        // Spray rays passing through center of mass
-        let ray = self.make_x_ray(center_of_mass);
-        let mut rays = Vec::new();
-        let mut ribs = Vec::new();
-        for i in 0..360 {
-            //for i in 0..1 {
-            let rot = Affine::rotate_about((i as f64).to_radians(), center_of_mass);
+    /// Spray rays from origin, stopping at the nearest path segment, and the ribs they find
+    fn cast_rays_around(
+        &self,
+        work: &Work,
+        origin: Point,
+        rays: &mut Vec<Line>,
+        ribs: &mut Vec<Line>,
+    ) -> Result<(), Budget> {
+        let ray = self.make_x_ray(origin);
+        let ray_count = self.options.ray_count;
+        for i in 0..ray_count {
+            work.check()?;
+            let rot =
+                Affine::rotate_about((i as f64 * 360.0 / ray_count as f64).to_radians(), origin);
             let ray = rot
                 * Line {
-                    p0: center_of_mass,
+                    p0: origin,
                     p1: ray.p1,
                 };
 
             // Find the nearest intersection with a segment, if any
             let Some((isct, seg)) = self
-                .path
-                .segments()
-                .flat_map(|s| s.intersect_line(ray).into_iter().map(move |i| (i, s)))
+                .intersections(work, ray)
+                .into_iter()
                 .reduce(|acc, e| if acc.0.line_t <= e.0.line_t { acc } else { e })
             else {
                 // Swing and a miss
@@ -304,18 +617,17 @@ impl WidthReader {
                 continue;
             };
 
-            // Find the next nearest intersection along the normal away from center of mass
+            // Find the next nearest intersection along the normal away from origin
             let (pt, tan) = seg.tangent(isct.segment_t);
             let normal1 = tan.turn_90();
             let normal2 = -normal1;
             let pn1 = pt + normal1;
             let pn2 = pt + normal2;
-            let away_from_center =
-                if (pn1 - center_of_mass).length() > (pn2 - center_of_mass).length() {
-                    normal1
-                } else {
-                    normal2
-                };
+            let away_from_center = if (pn1 - origin).length() > (pn2 - origin).length() {
+                normal1
+            } else {
+                normal2
+            };
 
             // If away from center is wildly divergent from ray discard it
             // This helps with things like Kablammo taking readings from outcrops
@@ -326,64 +638,255 @@ impl WidthReader {
             // }
 
             // record our ray as far as the point of intersection
-            rays.push(Line {
-                p0: center_of_mass,
-                p1: pt,
-            });
+            rays.push(Line { p0: origin, p1: pt });
 
             // new ray perpendicular to isct
             let ray = Affine::rotate_about(away_from_center.angle(), pt) * self.make_x_ray(pt);
 
             // Keep the nearest candidate only
-            if let Some(nearest_candidate) =
-                self.ray_to_inked_segments(ray)
-                    .into_iter()
-                    .reduce(|best, candidate| {
-                        if best.nearest(pt, 0.000001).distance_sq
-                            <= candidate.nearest(pt, 0.000001).distance_sq
-                        {
-                            best
-                        } else {
-                            candidate
-                        }
-                    })
+            if let Some(nearest_candidate) = self
+                .ray_to_inked_segments(work, ray)
+                .into_iter()
+                .reduce(|best, candidate| {
+                    if best.nearest(pt, 0.000001).distance_sq
+                        <= candidate.nearest(pt, 0.000001).distance_sq
+                    {
+                        best
+                    } else {
+                        candidate
+                    }
+                })
             {
                 ribs.push(nearest_candidate);
             }
         }
-
-        WidthCandidates::new(&self.path, rays, ribs)
+        Ok(())
     }
 
-    pub fn cast_rays_from_all_segments(&self) -> WidthCandidates {
+    pub fn cast_rays_from_all_segments(&self) -> Result<WidthCandidates, StrokeContrastError> {
+        check_ray_count(
+            &self.options,
+            self.edges.len() * self.options.samples_per_segment,
+        )
+        .map_err(|budget| StrokeContrastError::BudgetExceeded {
+            budget,
+            partial: None,
+        })?;
+        let work = self.start_work();
         let mut rays = Vec::new();
         let mut ribs = Vec::new();
-        for segment in self.path.segments() {
-            for i in 0..10 {
-                let t = 0.1 * i as f64;
+        for segment in self.edges.segments() {
+            for i in 0..self.options.samples_per_segment {
+                if let Err(budget) = work.check() {
+                    return Err(self.budget_exceeded(budget, &work, rays, ribs));
+                }
+                let t = i as f64 / self.options.samples_per_segment as f64;
                 let (on_path, tangent) = segment.tangent(t);
                 let normal = tangent.turn_90();
                 let ray = Affine::rotate_about(normal.angle(), on_path) * self.make_x_ray(on_path);
                 rays.push(ray);
                 // Keep all the candidates
-                ribs.extend(self.ray_to_inked_segments(ray));
+                ribs.extend(self.ray_to_inked_segments(&work, ray));
+            }
+        }
+        WidthCandidates::new(self, rays, ribs, &work)
+    }
+
+    /// Fit maximal inscribed circles along an approximate medial axis (skeleton) of the outline.
+    ///
+    /// Uses the shrinking ball algorithm: for points sampled along the outline we start with
+    /// a huge ball touching the outline at the sample, tangent to it, and shrink it until no part of
+    /// the outline falls inside. The centers of the resulting balls approximate the medial axis and
+    /// their diameters the stroke width along it.
+    pub fn fit_circles_along_medial_axis(&self) -> Result<WidthCandidates, StrokeContrastError> {
+        check_ray_count(
+            &self.options,
+            self.edges.len() * self.options.samples_per_segment,
+        )
+        .map_err(|budget| StrokeContrastError::BudgetExceeded {
+            budget,
+            partial: None,
+        })?;
+        let work = self.start_work();
+        let mut rays = Vec::new();
+        let mut ribs = Vec::new();
+        for segment in self.edges.segments() {
+            for i in 0..self.options.samples_per_segment {
+                if let Err(budget) = work.check() {
+                    return Err(StrokeContrastError::BudgetExceeded {
+                        budget,
+                        partial: WidthCandidates::from_ribs(rays, ribs).ok().map(Box::new),
+                    });
+                }
+                let t = i as f64 / self.options.samples_per_segment as f64;
+                let (on_path, tangent) = segment.tangent(t);
+                if is_degenerate(tangent) {
+                    continue;
+                }
+                let Some(inward) = self.inward_normal(&work, on_path, tangent) else {
+                    continue;
+                };
+                let Some((center, radius, touch)) = self.shrink_ball(&work, on_path, inward) else {
+                    continue;
+                };
+
+                // Near the end of a branch of the skeleton the ball touches the outline twice in
+                // quick succession, e.g. rounding a corner. That measures the corner, not a stroke,
+                // so only keep balls that touch roughly opposite walls.
+                let object_angle = (on_path - center).angle() - (touch - center).angle();
+                let object_angle = object_angle.sin().atan2(object_angle.cos()).abs();
+                if object_angle < self.options.min_object_angle.to_radians() {
+                    continue;
+                }
+                if radius <= self.options.min_radius {
+                    debug!("Suspiciously small inscribed circle at {center:?}");
+                    continue;
+                }
+
+                rays.push(Line::new(on_path, center));
+                ribs.push((
+                    Line::new(on_path, on_path + 2.0 * radius * inward),
+                    Circle::new(center, radius),
+                ));
+            }
+        }
+        WidthCandidates::from_ribs(rays, ribs)
+    }
+
+    /// Unit normal to the tangent that points into ink, if either does
+    fn inward_normal(&self, work: &Work, on_path: Point, tangent: Vec2) -> Option<Vec2> {
+        let normal = tangent.normalize().turn_90();
+        if self.is_inked(work, on_path + self.ray_width * normal) {
+            Some(normal)
+        } else if self.is_inked(work, on_path - self.ray_width * normal) {
+            Some(-normal)
+        } else {
+            None
+        }
+    }
+
+    /// Shrink a ball tangent to the outline at on_path, centered along inward, until it is empty.
+    ///
+    /// Returns (center, radius, the other point the ball touches).
+    fn shrink_ball(
+        &self,
+        work: &Work,
+        on_path: Point,
+        inward: Vec2,
+    ) -> Option<(Point, f64, Point)> {
+        let mut radius = self.max_dim;
+        let mut touch = None;
+        for _ in 0..64 {
+            let center = on_path + radius * inward;
+            let nearest = self.nearest_on_path(work, center);
+            if (nearest - center).length() >= radius - 0.000001 * self.max_dim {
+                return touch.map(|touch| (center, radius, touch));
+            }
+
+            // The ball through on_path and nearest, tangent at on_path
+            let chord = nearest - on_path;
+            let along_normal = chord.dot(inward);
+            if along_normal <= 0.0 {
+                return None;
+            }
+            radius = chord.hypot2() / (2.0 * along_normal);
+            touch = Some(nearest);
+        }
+        None
+    }
+
+    /// Fit a circle into the ink around the midpoint of rib.
+    ///
+    /// The circle is the largest centered on the midpoint, its radius the distance to the nearest
+    /// edge. If that spans too little of the rib the rib doesn't cross a stroke and None is
+    /// returned. With [CircleFit::Maximal] it is then grown, away from that edge, into the largest
+    /// inscribed circle touching it, which also touches the opposite wall. The rib is then
+    /// replaced by the diameter through the two.
+    fn fit_circle(&self, work: &Work, rib: Line) -> Option<(Line, Circle)> {
+        let mid = rib.midpoint();
+        // The rib ends on the outline so its midpoint is only uninked if the rib is degenerate
+        if !self.is_inked(work, mid) {
+            return None;
+        }
+        let nearest = self.nearest_on_path(work, mid);
+        let radius = (mid - nearest).length();
+        if 2.0 * radius < self.options.min_rib_coverage * rib.length() {
+            return None;
+        }
+        match self.options.circle_fit {
+            CircleFit::Centered => Some((rib, Circle::new(mid, radius))),
+            CircleFit::Maximal => {
+                if is_degenerate(mid - nearest) {
+                    return None;
+                }
+                // The nearest point is where the outline's normal passes through mid
+                let inward = (mid - nearest).normalize();
+                let (center, radius, _) = self.shrink_ball(work, nearest, inward)?;
+                Some((
+                    Line::new(nearest, nearest + 2.0 * radius * inward),
+                    Circle::new(center, radius),
+                ))
             }
         }
-        WidthCandidates::new(&self.path, rays, ribs)
+    }
+
+    /// Whether pt is inked under our fill rule
+    fn is_inked(&self, work: &Work, pt: Point) -> bool {
+        work.charge_path_query();
+        self.options.fill_rule.is_inked(self.index.winding(pt))
+    }
+
+    /// Every intersection of line with the edges of the ink along with the segment intersected
+    fn intersections(&self, work: &Work, line: Line) -> Vec<(LineIntersection, PathSeg)> {
+        work.charge_path_query();
+        self.edges.intersect_line(line)
+    }
+
+    fn nearest_on_path(&self, work: &Work, pt: Point) -> Point {
+        work.charge_path_query();
+        self.edges
+            .nearest(pt, 0.000001)
+            .expect("Path to have segments")
+    }
+
+    /// Start tracking work for a new measurement, already charged for preparing the outline
+    fn start_work(&self) -> Work {
+        Work::after(self.spent, &self.options, self.index.len())
+    }
+
+    /// Give up on a measurement that ran out of budget.
+    ///
+    /// Up to [MAX_PARTIAL_RIBS] of the ribs found so far, evenly spread so every part of the outline
+    /// reached is represented, are fit under a fraction of the budget to produce a partial result.
+    fn budget_exceeded(
+        &self,
+        budget: Budget,
+        work: &Work,
+        rays: Vec<Line>,
+        ribs: Vec<Line>,
+    ) -> StrokeContrastError {
+        let step = ribs.len().div_ceil(MAX_PARTIAL_RIBS).max(1);
+        let ribs = ribs.into_iter().step_by(step).collect();
+        let partial = match WidthCandidates::new(self, rays, ribs, &work.partial()) {
+            Ok(candidates) => Some(Box::new(candidates)),
+            Err(StrokeContrastError::BudgetExceeded { partial, .. }) => partial,
+            Err(_) => None,
+        };
+        StrokeContrastError::BudgetExceeded { budget, partial }
     }
 
     // Returns one line segment per continuously inked area encountered
-    fn ray_to_inked_segments(&self, ray: Line) -> Vec<Line> {
+    fn ray_to_inked_segments(&self, work: &Work, ray: Line) -> Vec<Line> {
         let mut intersections = self
-            .path
-            .segments()
-            .flat_map(|s| s.intersect_line(ray).into_iter())
+            .intersections(work, ray)
+            .into_iter()
+            .map(|(isct, _)| isct)
             // Discard interior intersections, e.g. those where we're inked on both sides
             .filter(|isct| {
                 let before = ray.eval(isct.line_t - 0.00001);
                 let after = ray.eval(isct.line_t + 0.00001);
-                let filled_before = self.path.winding(before) != 0;
-                let filled_after = self.path.winding(after) != 0;
+                let filled_before = self.is_inked(work, before);
+                let filled_after = self.is_inked(work, after);
                 // Discard if inked before and after
                 !(filled_before && filled_after)
             })
@@ -404,7 +907,7 @@ impl WidthReader {
                 p1: ray.eval(window[1].line_t),
             };
             // Retain only segments through inked regions
-            if self.path.winding(segment.midpoint()) != 0 {
+            if self.is_inked(work, segment.midpoint()) {
                 results.push(segment);
             }
         }
@@ -434,8 +937,12 @@ impl WidthReader {
         svg.push_str(&format!("{:02} ", self.bbox.width()));
         svg.push_str(&format!("{:02}", self.bbox.height()));
         svg.push_str(r#"">"#);
-        svg.push_str("\n");
-        svg.push_str("  <path fill=\"darkgray\" d=\"");
+        svg.push('\n');
+        svg.push_str(&format!("  <!-- {:?} -->\n", self.options));
+        svg.push_str(&format!(
+            "  <path fill=\"darkgray\" fill-rule=\"{}\" d=\"",
+            self.options.fill_rule.svg_name()
+        ));
         svg.push_str(&self.path.to_svg());
         svg.push_str("\" />\n");
 
@@ -483,58 +990,67 @@ pub struct WidthCandidates {
     pub ribs: Vec<(Line, Circle)>,
     pub min_width: f64,
     pub max_width: f64,
+    /// The distribution of rib diameters
+    pub distribution: WidthDistribution,
+    /// Widths by stroke orientation
+    pub stems: StemWidths,
+    pub contrast: Contrast,
 }
 
 impl WidthCandidates {
-    fn new(path: &BezPath, rays: Vec<Line>, rib_candidates: Vec<Line>) -> Self {
-        // For each each candidate fit a circle around it's midpoint into the inked shape
-        let mut min_width = f64::MAX;
-        let mut max_width = f64::MIN;
-        let ribs = rib_candidates
-            .into_iter()
-            .filter_map(|candidate| {
-                // See if a circle around the midpoint of our line goes into unpainted area
-                let mid = candidate.midpoint();
-
-                // Try from the full line through to almost nothing
-                // Often times the end (t=0) reports winding 0
-                let mut t = 0.0;
-                let mut inc = 0.001;
-                let mut solution = None;
-                while solution.is_none() && t <= 0.1 {
-                    // If points around mid are all inked take this as a valid result
-                    // TODO: not brute force :)
-                    let pt = candidate.eval(t);
-                    let samples = 90;
-                    if (0..samples).all(|i| {
-                        let rot = i as f64 * 360.0 / samples as f64;
-                        let pt = Affine::rotate_about(rot.to_radians(), mid) * pt;
-                        path.winding(pt) != 0
-                    }) {
-                        let radius = (pt - mid).length();
-                        if radius > 1.0 {
-                            let candidate_length = 2.0 * radius;
-                            min_width = min_width.min(candidate_length);
-                            max_width = max_width.max(candidate_length);
-                            solution = Some((candidate, Circle::new(mid, radius)));
-                        } else {
-                            // Still getting very short line segments sometimes
-                            debug!("Suspiciously small rib, {candidate:?}");
-                        }
-                    }
-                    t += inc;
-                    inc += inc;
-                }
-                solution
-            })
-            .collect::<Vec<_>>();
+    fn new(
+        reader: &WidthReader,
+        rays: Vec<Line>,
+        rib_candidates: Vec<Line>,
+        work: &Work,
+    ) -> Result<Self, StrokeContrastError> {
+        // For each each candidate fit a circle into the inked shape
+        let options = &reader.options;
+        let mut ribs = Vec::new();
+        for candidate in rib_candidates {
+            if let Err(budget) = work.check() {
+                return Err(StrokeContrastError::BudgetExceeded {
+                    budget,
+                    partial: Self::from_ribs(rays, ribs).ok().map(Box::new),
+                });
+            }
 
-        Self {
+            match reader.fit_circle(work, candidate) {
+                Some(rib) if rib.1.radius > options.min_radius => ribs.push(rib),
+                // Still getting very short line segments sometimes
+                Some(_) => debug!("Suspiciously small rib, {candidate:?}"),
+                None => (),
+            }
+        }
+
+        Self::from_ribs(rays, ribs)
+    }
+
+    /// For when the circles were fit as part of finding the ribs
+    fn from_ribs(rays: Vec<Line>, ribs: Vec<(Line, Circle)>) -> Result<Self, StrokeContrastError> {
+        if ribs.is_empty() {
+            return Err(StrokeContrastError::NoRibsFound);
+        }
+        let distribution =
+            WidthDistribution::new(ribs.iter().map(|(_, circle)| 2.0 * circle.radius).collect());
+        let min_width = distribution.widths[0];
+        let max_width = distribution.widths[distribution.widths.len() - 1];
+        let contrast = Contrast::new(&distribution, &ribs);
+        let stems = StemWidths::new(&ribs);
+        Ok(Self {
             rays,
             ribs,
             min_width,
             max_width,
-        }
+            distribution,
+            stems,
+            contrast,
+        })
+    }
+
+    /// The axis of stress implied by the ribs, see [stress_angle]
+    pub fn stress(&self) -> Option<Stress> {
+        stress_angle(&self.ribs)
     }
 }
 
@@ -544,3 +1060,378 @@ pub fn normalization_scale(font: &skrifa::FontRef) -> f64 {
     let upem = head.units_per_em() as f64;
     1000.0 / upem
 }
+
+#[cfg(test)]
+mod tests {
+    use kurbo::{BezPath, Circle, CubicBez, Line, ParamCurve, PathSeg, Point, Rect, Shape, Vec2};
+
+    use crate::{
+        CircleFit, MeasureOptions, SegmentSelection, Tangent, WidthReader, centroid,
+        glyph_transforms,
+    };
+
+    /// Direction of travel through the curve at t, by finite difference
+    fn travel(cubic: CubicBez, t: f64) -> Vec2 {
+        let h = 1e-6;
+        let (t0, t1) = ((t - h).max(0.0), (t + h).min(1.0));
+        (cubic.eval(t1) - cubic.eval(t0)).normalize()
+    }
+
+    fn assert_same_direction(actual: Vec2, expected: Vec2) {
+        let actual = actual.normalize();
+        assert!(
+            (actual - expected).hypot() < 1e-3,
+            "{actual:?} != {expected:?}"
+        );
+    }
+
+    #[test]
+    fn cubic_tangent() {
+        let cubic = CubicBez::new((0.0, 0.0), (0.0, 100.0), (100.0, 100.0), (100.0, 0.0));
+        let (pt, tan) = PathSeg::Cubic(cubic).tangent(0.5);
+        assert_eq!(Point::new(50.0, 75.0), pt);
+        assert_same_direction(tan, travel(cubic, 0.5));
+    }
+
+    #[test]
+    fn cubic_tangent_first_control_on_start() {
+        let cubic = CubicBez::new((0.0, 0.0), (0.0, 0.0), (100.0, 100.0), (200.0, 0.0));
+        let (pt, tan) = PathSeg::Cubic(cubic).tangent(0.0);
+        assert_eq!(Point::new(0.0, 0.0), pt);
+        assert_same_direction(tan, travel(cubic, 0.0));
+    }
+
+    #[test]
+    fn cubic_tangent_second_control_on_end() {
+        let cubic = CubicBez::new((0.0, 0.0), (100.0, 100.0), (200.0, 0.0), (200.0, 0.0));
+        let (pt, tan) = PathSeg::Cubic(cubic).tangent(1.0);
+        assert_eq!(Point::new(200.0, 0.0), pt);
+        assert_same_direction(tan, travel(cubic, 1.0));
+    }
+
+    #[test]
+    fn cubic_tangent_both_controls_on_ends() {
+        let cubic = CubicBez::new((0.0, 0.0), (0.0, 0.0), (300.0, 400.0), (300.0, 400.0));
+        for t in [0.0, 1.0] {
+            let (_, tan) = PathSeg::Cubic(cubic).tangent(t);
+            assert_same_direction(tan, Vec2::new(0.6, 0.8));
+        }
+    }
+
+    fn assert_near(actual: Point, expected: Point) {
+        assert!(
+            (actual - expected).hypot() < 0.01,
+            "{actual:?} != {expected:?}"
+        );
+    }
+
+    #[test]
+    fn centroid_of_rect() {
+        let rect = Rect::new(10.0, 20.0, 110.0, 60.0).to_path(0.1);
+        assert_near(centroid(&rect).unwrap(), Point::new(60.0, 40.0));
+    }
+
+    #[test]
+    fn centroid_of_ring() {
+        // 200x100 with a 50x80 hole, wound the other way, off to the left
+        let mut path = Rect::new(0.0, 0.0, 200.0, 100.0).to_path(0.1);
+        path.extend(
+            Rect::new(10.0, 10.0, 60.0, 90.0)
+                .to_path(0.1)
+                .reverse_subpaths(),
+        );
+        assert_near(centroid(&path).unwrap(), Point::new(116.25, 50.0));
+    }
+
+    #[test]
+    fn centroid_of_cubic_circles() {
+        // Circles are drawn with cubics. A disc of radius 100 less one of radius 50, 30 to the
+        // right, balances 10 to the left.
+        let mut path = Circle::new((0.0, 0.0), 100.0).to_path(0.001);
+        path.extend(
+            Circle::new((30.0, 0.0), 50.0)
+                .to_path(0.001)
+                .reverse_subpaths(),
+        );
+        assert!(path.segments().all(|seg| matches!(seg, PathSeg::Cubic(_))));
+        assert_near(centroid(&path).unwrap(), Point::new(-10.0, 0.0));
+    }
+
+    #[test]
+    fn centroid_of_nothing() {
+        assert_eq!(None, centroid(&BezPath::new()));
+    }
+
+    #[test]
+    fn glyphs_placed_by_advance_and_offset() {
+        // A base, a mark pulled back over it and raised, then a second base
+        let transforms = glyph_transforms([
+            (Vec2::ZERO, Vec2::new(500.0, 0.0)),
+            (Vec2::new(-250.0, 100.0), Vec2::ZERO),
+            (Vec2::ZERO, Vec2::new(400.0, 0.0)),
+        ]);
+        // Font units are y-up, we measure y-down
+        assert_eq!(
+            vec![
+                Point::new(0.0, -700.0),
+                Point::new(250.0, -800.0),
+                Point::new(500.0, -700.0),
+            ],
+            transforms
+                .iter()
+                .map(|t| *t * Point::new(0.0, 700.0))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn glyphs_placed_by_vertical_advance() {
+        // Vertical text advances down, i.e. negative y in font units
+        let transforms = glyph_transforms([
+            (Vec2::new(-500.0, -880.0), Vec2::new(0.0, -1000.0)),
+            (Vec2::new(-500.0, -880.0), Vec2::new(0.0, -1000.0)),
+        ]);
+        assert_eq!(
+            vec![Point::new(-500.0, 880.0), Point::new(-500.0, 1880.0)],
+            transforms
+                .iter()
+                .map(|t| *t * Point::ORIGIN)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    /// A '#' with 200 unit bars, a single outline around a square counter
+    fn merged_hash() -> BezPath {
+        let mut path = BezPath::new();
+        path.move_to((200.0, 0.0));
+        for pt in [
+            (400.0, 0.0),
+            (400.0, 200.0),
+            (600.0, 200.0),
+            (600.0, 0.0),
+            (800.0, 0.0),
+            (800.0, 200.0),
+            (1000.0, 200.0),
+            (1000.0, 400.0),
+            (800.0, 400.0),
+            (800.0, 600.0),
+            (1000.0, 600.0),
+            (1000.0, 800.0),
+            (800.0, 800.0),
+            (800.0, 1000.0),
+            (600.0, 1000.0),
+            (600.0, 800.0),
+            (400.0, 800.0),
+            (400.0, 1000.0),
+            (200.0, 1000.0),
+            (200.0, 800.0),
+            (0.0, 800.0),
+            (0.0, 600.0),
+            (200.0, 600.0),
+            (200.0, 400.0),
+            (0.0, 400.0),
+            (0.0, 200.0),
+            (200.0, 200.0),
+        ] {
+            path.line_to(pt);
+        }
+        path.close_path();
+        path.extend(
+            Rect::new(400.0, 400.0, 600.0, 600.0)
+                .to_path(0.1)
+                .reverse_subpaths(),
+        );
+        path
+    }
+
+    /// The same '#' drawn as four overlapping bars
+    fn overlapping_hash() -> BezPath {
+        let mut path = BezPath::new();
+        for bar in [
+            Rect::new(200.0, 0.0, 400.0, 1000.0),
+            Rect::new(600.0, 0.0, 800.0, 1000.0),
+            Rect::new(0.0, 200.0, 1000.0, 400.0),
+            Rect::new(0.0, 600.0, 1000.0, 800.0),
+        ] {
+            path.extend(bar.to_path(0.1).iter());
+        }
+        path
+    }
+
+    /// A square ring 200 units thick, a single outline with a counter cut out of it
+    fn merged_ring() -> BezPath {
+        let mut path = Rect::new(0.0, 0.0, 1000.0, 1000.0).to_path(0.1);
+        path.extend(
+            Rect::new(200.0, 200.0, 800.0, 800.0)
+                .to_path(0.1)
+                .reverse_subpaths(),
+        );
+        path
+    }
+
+    /// The same ring drawn as four bars that overlap, and share sides, at the corners
+    fn overlapping_ring() -> BezPath {
+        let mut path = BezPath::new();
+        for bar in [
+            Rect::new(0.0, 0.0, 200.0, 1000.0),
+            Rect::new(800.0, 0.0, 1000.0, 1000.0),
+            Rect::new(0.0, 0.0, 1000.0, 200.0),
+            Rect::new(0.0, 800.0, 1000.0, 1000.0),
+        ] {
+            path.extend(bar.to_path(0.1).iter());
+        }
+        path
+    }
+
+    #[test]
+    fn overlapping_contours_measure_as_merged() {
+        for ((merged, overlapping), method) in [
+            (merged_hash(), overlapping_hash()),
+            (merged_ring(), overlapping_ring()),
+        ]
+        .into_iter()
+        .flat_map(|shapes| {
+            [
+                SegmentSelection::CenterOfMass,
+                SegmentSelection::AllSegments,
+                SegmentSelection::MedialAxis,
+            ]
+            .map(|method| (shapes.clone(), method))
+        }) {
+            let reader = WidthReader::from_path(overlapping.clone(), Default::default()).unwrap();
+            assert!(reader.contours.overlapping > 0, "{}", reader.contours);
+            assert!(!reader.contours.unmerged, "{}", reader.contours);
+            let measure = |path| {
+                WidthReader::from_path(path, Default::default())
+                    .unwrap()
+                    .measure(method)
+                    .unwrap()
+            };
+            let (merged, overlapping) = (measure(merged), measure(overlapping));
+            for (expected, actual) in [
+                (merged.min_width, overlapping.min_width),
+                (merged.max_width, overlapping.max_width),
+            ] {
+                assert!(
+                    (expected - actual).abs() < 1.0,
+                    "{method:?} {expected:.2} != {actual:.2}"
+                );
+            }
+        }
+    }
+
+    fn medial_axis_widths(path: BezPath) -> (f64, f64) {
+        let candidates = WidthReader::from_path(path, Default::default())
+            .unwrap()
+            .fit_circles_along_medial_axis()
+            .unwrap();
+        (candidates.min_width, candidates.max_width)
+    }
+
+    fn assert_width(expected: f64, actual: f64) {
+        assert!(
+            (expected - actual).abs() < 1.0,
+            "{actual:.2} != {expected:.2}"
+        );
+    }
+
+    #[test]
+    fn medial_axis_of_round_ring() {
+        // Radius 300 less radius 200 leaves a stroke 100 wide all the way round
+        let mut path = Circle::new((0.0, 0.0), 300.0).to_path(0.1);
+        path.extend(
+            Circle::new((0.0, 0.0), 200.0)
+                .to_path(0.1)
+                .reverse_subpaths(),
+        );
+        let (min, max) = medial_axis_widths(path);
+        assert_width(100.0, min);
+        assert_width(100.0, max);
+    }
+
+    #[test]
+    fn medial_axis_of_square_ring() {
+        // Sides 50 wide, top and bottom 200 tall
+        let mut path = Rect::new(0.0, 0.0, 600.0, 1000.0).to_path(0.1);
+        path.extend(
+            Rect::new(50.0, 200.0, 550.0, 800.0)
+                .to_path(0.1)
+                .reverse_subpaths(),
+        );
+        let (min, max) = medial_axis_widths(path);
+        assert_width(50.0, min);
+        assert_width(200.0, max);
+    }
+
+    /// A horizontal stroke 100 thick at the left tapering out to 500 thick at the right
+    fn tapering_stroke(options: MeasureOptions) -> WidthReader {
+        let mut path = BezPath::new();
+        path.move_to((0.0, 0.0));
+        path.line_to((1000.0, 0.0));
+        path.line_to((1000.0, 500.0));
+        path.line_to((0.0, 100.0));
+        path.close_path();
+        WidthReader::from_path(path, options).unwrap()
+    }
+
+    #[test]
+    fn maximal_circle_outgrows_centered_on_taper() {
+        let fit = |circle_fit| {
+            let reader = tapering_stroke(MeasureOptions {
+                circle_fit,
+                ..Default::default()
+            });
+            let work = reader.start_work();
+            let (_, circle) = reader
+                .fit_circle(&work, Line::new((500.0, 0.0), (500.0, 300.0)))
+                .unwrap();
+            circle.radius
+        };
+        let centered = fit(CircleFit::Centered);
+        let maximal = fit(CircleFit::Maximal);
+        // The centered circle is held back by the sloping top, 150 / sqrt(1.16) from the midpoint
+        assert!((centered - 139.27).abs() < 0.01, "{centered:.2}");
+        assert!(maximal > centered + 5.0, "{maximal:.2} <= {centered:.2}");
+    }
+
+    #[test]
+    fn low_coverage_rib_is_dropped() {
+        // Corner to corner along a bar 100 thick the centered circle spans a tenth of the rib
+        let rib = Line::new((0.0, 0.0), (1000.0, 100.0));
+        let fit = |min_rib_coverage| {
+            let reader = WidthReader::from_path(
+                Rect::new(0.0, 0.0, 1000.0, 100.0).to_path(0.1),
+                MeasureOptions {
+                    min_rib_coverage,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+            let work = reader.start_work();
+            reader
+                .fit_circle(&work, rib)
+                .map(|(_, circle)| circle.radius)
+        };
+        assert_eq!(None, fit(MeasureOptions::default().min_rib_coverage));
+        assert_eq!(Some(50.0), fit(0.05));
+    }
+
+    #[test]
+    fn counter_seed_level_with_vertices() {
+        // A square with a diamond counter, the centroid of the counter is level with two of its
+        // corners
+        let mut path = Rect::new(0.0, 0.0, 1000.0, 1000.0).to_path(0.1);
+        path.move_to((500.0, 200.0));
+        path.line_to((200.0, 500.0));
+        path.line_to((500.0, 800.0));
+        path.line_to((800.0, 500.0));
+        path.close_path();
+        let reader = WidthReader::from_path(path, Default::default()).unwrap();
+        let seeds = reader.counter_seeds(&reader.start_work()).unwrap();
+        assert_eq!(1, seeds.len(), "{seeds:?}");
+        assert!(
+            (seeds[0] - Point::new(500.0, 500.0)).hypot() < 1e-6,
+            "{seeds:?}"
+        );
+    }
+}
@@ -0,0 +1,494 @@
+use std::collections::BTreeSet;
+
+use fontdrasil::coords::{UserCoord, UserLocation};
+use log::warn;
+use skrifa::{Axis, MetadataProvider, Tag, raw::TableProvider};
+
+/// How to choose the points in a variable font's design space to measure at
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+pub enum Sampling {
+    /// Multiples of wght step plus wght min, default and max, every other axis at default
+    #[default]
+    Weight,
+    /// The default location plus the min and max of each axis, other axes at default
+    Extremes,
+    /// Every combination of evenly spaced values on each axis
+    Grid,
+    /// The fvar named instances
+    NamedInstances,
+}
+
+/// A fvar named instance, e.g. Bold, and where it is
+#[derive(Debug, Clone, PartialEq)]
+pub struct NamedLocation {
+    /// The subfamily name from the name table
+    pub name: String,
+    pub location: UserLocation,
+}
+
+/// Where to measure a variable font
+#[derive(Debug, Clone, Copy, PartialEq, clap::Args)]
+#[command(about = None, long_about = None)]
+pub struct LocationOptions {
+    /// How to choose locations to measure at
+    #[arg(long, value_enum, default_value_t = LocationOptions::default().sampling)]
+    pub sampling: Sampling,
+
+    /// Step between weights for weight sampling, e.g. 50 to add 350, 450, etc
+    #[arg(long, default_value_t = LocationOptions::default().wght_step)]
+    pub wght_step: f64,
+
+    /// Values per axis for grid sampling, including min and max
+    #[arg(long, default_value_t = LocationOptions::default().grid_steps)]
+    pub grid_steps: usize,
+
+    /// Most locations to measure at, a grid over many axes gets big fast.
+    /// Evenly thinned if exceeded, keeping the default and the min and max of every axis.
+    #[arg(long, default_value_t = LocationOptions::default().max_locations)]
+    pub max_locations: usize,
+}
+
+impl Default for LocationOptions {
+    fn default() -> Self {
+        Self {
+            sampling: Sampling::Weight,
+            wght_step: 100.0,
+            grid_steps: 3,
+            max_locations: 64,
+        }
+    }
+}
+
+pub fn locations_of_interest(
+    font: &skrifa::FontRef,
+    options: &LocationOptions,
+) -> Vec<UserLocation> {
+    if font.fvar().is_err() {
+        return vec![UserLocation::new()];
+    }
+    let axes = visible_axes(font);
+    let mut result = match options.sampling {
+        Sampling::Weight => weight_steps(font, options.wght_step),
+        Sampling::Extremes => extremes(&axes),
+        Sampling::Grid => grid(&axes, options.grid_steps, options.max_locations),
+        Sampling::NamedInstances => named_instances(font)
+            .into_iter()
+            .map(|named| named.location)
+            .collect(),
+    };
+    if result.is_empty() {
+        result.push(UserLocation::new());
+    }
+    thin(result, options.max_locations, &axes)
+}
+
+/// The user space range of an axis
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct AxisRange {
+    tag: Tag,
+    min: f64,
+    default: f64,
+    max: f64,
+}
+
+impl AxisRange {
+    /// Where loc is on this axis, default if loc doesn't say
+    fn coord(&self, loc: &UserLocation) -> f64 {
+        loc.get(self.tag)
+            .map(|coord| coord.to_f64())
+            .unwrap_or(self.default)
+    }
+}
+
+fn visible_axes(font: &skrifa::FontRef) -> Vec<AxisRange> {
+    font.axes()
+        .iter()
+        .filter(|a| !a.is_hidden())
+        .map(|a| AxisRange {
+            tag: a.tag(),
+            min: a.min_value() as f64,
+            default: a.default_value() as f64,
+            max: a.max_value() as f64,
+        })
+        .collect()
+}
+
+/// Canonical weights, multiples of step, within the axis range plus its exact min, default and max.
+///
+/// Locations are in user space, avar is applied when they are normalized, so a 250..850 axis
+/// yields 250, 300, 400, ..., 800, 850 regardless of how the designer mapped it.
+fn weight_steps(font: &skrifa::FontRef, step: f64) -> Vec<UserLocation> {
+    const WGHT_TAG: Tag = Tag::new(b"wght");
+    let Some(wght_axis) = font.axes().get_by_tag(WGHT_TAG) else {
+        return Vec::new();
+    };
+    weight_values(
+        wght_axis.min_value() as f64,
+        wght_axis.default_value() as f64,
+        wght_axis.max_value() as f64,
+        step,
+    )
+    .into_iter()
+    .map(|wght| {
+        let mut user = UserLocation::new();
+        user.insert(WGHT_TAG, UserCoord::new(wght));
+        user
+    })
+    .collect()
+}
+
+/// Multiples of step in min..=max plus min, default and max, ascending
+fn weight_values(min: f64, default: f64, max: f64, step: f64) -> Vec<f64> {
+    let mut weights = vec![min, default, max];
+    if step > 0.0 {
+        let (first, last) = ((min / step).ceil() as i64, (max / step).floor() as i64);
+        weights.extend((first..=last).map(|i| i as f64 * step));
+    }
+    weights.sort_by(f64::total_cmp);
+    weights.dedup_by(|a, b| (*a - *b).abs() < 1e-6);
+    weights
+}
+
+fn extremes(axes: &[AxisRange]) -> Vec<UserLocation> {
+    let mut result = vec![UserLocation::new()];
+    for axis in axes {
+        for value in [axis.min, axis.max] {
+            if value != axis.default {
+                let mut user = UserLocation::new();
+                user.insert(axis.tag, UserCoord::new(value));
+                result.push(user);
+            }
+        }
+    }
+    result
+}
+
+/// Every combination of steps evenly spaced values on each axis, at most max of them.
+///
+/// The grid can be astronomically large so it is never built in full. Combinations are numbered
+/// with the last axis varying fastest and, if there are more than max, only some are decoded:
+/// those [thin] keeps, the default and the first at each axis min and max, plus evenly strided
+/// ones to make up max.
+fn grid(axes: &[AxisRange], steps: usize, max: usize) -> Vec<UserLocation> {
+    // A single step is the default, which doesn't vary
+    let values = axes
+        .iter()
+        .filter(|_| steps > 1)
+        .map(|axis| {
+            let (min, max) = (axis.min, axis.max);
+            let values = (0..steps)
+                .map(|i| min + (max - min) * i as f64 / (steps - 1) as f64)
+                .collect::<Vec<_>>();
+            (axis, values)
+        })
+        .collect::<Vec<_>>();
+    // Saturating only makes the picks less even, every index still decodes
+    let total = values.iter().fold(1u128, |total, (_, values)| {
+        total.saturating_mul(values.len() as u128)
+    });
+    let index_of = |digits: &[usize]| {
+        digits
+            .iter()
+            .zip(values.iter())
+            .fold(0u128, |index, (digit, (_, values))| {
+                index
+                    .saturating_mul(values.len() as u128)
+                    .saturating_add(*digit as u128)
+            })
+    };
+    let location = |mut index: u128| {
+        let mut loc = UserLocation::new();
+        for (axis, values) in values.iter().rev() {
+            let len = values.len() as u128;
+            loc.insert(axis.tag, UserCoord::new(values[(index % len) as usize]));
+            index /= len;
+        }
+        loc
+    };
+
+    let max = max.max(1) as u128;
+    if total <= max {
+        return (0..total).map(location).collect();
+    }
+    let mut picked = BTreeSet::new();
+    // The first location at every axis min is every axis at min
+    picked.insert(0);
+    for i in 0..values.len() {
+        let mut digits = vec![0; values.len()];
+        digits[i] = values[i].1.len() - 1;
+        picked.insert(index_of(&digits));
+    }
+    let default = values
+        .iter()
+        .map(|(axis, values)| values.iter().position(|v| (v - axis.default).abs() < 0.01))
+        .collect::<Option<Vec<_>>>();
+    if let Some(default) = default {
+        picked.insert(index_of(&default));
+    }
+    let count = max.saturating_sub(picked.len() as u128);
+    for i in 0..count {
+        let mut index = i * (total / count) + i * (total % count) / count;
+        while picked.contains(&index) {
+            index = (index + 1) % total;
+        }
+        picked.insert(index);
+    }
+    picked.into_iter().map(location).collect()
+}
+
+/// The fvar named instances, with names resolved from the name table
+pub fn named_instances(font: &skrifa::FontRef) -> Vec<NamedLocation> {
+    let axes = font.axes();
+    font.named_instances()
+        .iter()
+        .map(|instance| {
+            let name = font
+                .localized_strings(instance.subfamily_name_id())
+                .english_or_first()
+                .map(|name| name.to_string())
+                .unwrap_or_else(|| format!("{}", instance.subfamily_name_id()));
+            let mut location = UserLocation::new();
+            for (axis, value) in axes.iter().zip(instance.user_coords()) {
+                location.insert(axis.tag(), UserCoord::new(value as f64));
+            }
+            NamedLocation { name, location }
+        })
+        .collect()
+}
+
+/// The name of the named instance at loc, if there is one.
+///
+/// Axes loc doesn't mention are taken to be at default.
+pub fn instance_name(font: &skrifa::FontRef, loc: &UserLocation) -> Option<String> {
+    let axes = font.axes();
+    let coord = |loc: &UserLocation, axis: &Axis| {
+        loc.get(axis.tag())
+            .map(|coord| coord.to_f64())
+            .unwrap_or(axis.default_value() as f64)
+    };
+    named_instances(font)
+        .into_iter()
+        .find(|named| {
+            axes.iter()
+                .all(|axis| (coord(loc, &axis) - coord(&named.location, &axis)).abs() < 0.01)
+        })
+        .map(|named| named.name)
+}
+
+/// True if loc is the default instance, axes it omits are at default
+pub fn is_default_location(font: &skrifa::FontRef, loc: &UserLocation) -> bool {
+    font.axes().iter().all(|axis| {
+        loc.get(axis.tag())
+            .is_none_or(|coord| (coord.to_f64() - axis.default_value() as f64).abs() < 0.01)
+    })
+}
+
+/// Keep at most max of locations, in their original order.
+///
+/// The default and, for each axis, the first location at its min and at its max are kept so the
+/// result still spans the design space. The rest of max is spread evenly over the others.
+fn thin(locations: Vec<UserLocation>, max: usize, axes: &[AxisRange]) -> Vec<UserLocation> {
+    let len = locations.len();
+    let max = max.max(1);
+    if len <= max {
+        return locations;
+    }
+    warn!("{len} locations exceeds the limit of {max}, thinning");
+
+    let is_at =
+        |loc: &UserLocation, axis: &AxisRange, value: f64| (axis.coord(loc) - value).abs() < 0.01;
+    let mut keep = vec![false; len];
+    if let Some(i) = locations
+        .iter()
+        .position(|loc| axes.iter().all(|axis| is_at(loc, axis, axis.default)))
+    {
+        keep[i] = true;
+    }
+    for axis in axes {
+        for value in [axis.min, axis.max] {
+            if let Some(i) = locations.iter().position(|loc| is_at(loc, axis, value)) {
+                keep[i] = true;
+            }
+        }
+    }
+
+    // Evenly pick count of indices, from those we must keep if they alone exceed max
+    let must_keep = keep.iter().filter(|k| **k).count();
+    let (from, count) = if must_keep >= max {
+        let from = (0..len).filter(|i| keep[*i]).collect::<Vec<_>>();
+        keep = vec![false; len];
+        (from, max)
+    } else {
+        ((0..len).filter(|i| !keep[*i]).collect(), max - must_keep)
+    };
+    for i in 0..count {
+        keep[from[i * from.len() / count]] = true;
+    }
+
+    locations
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(loc, keep)| keep.then_some(loc))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use fontdrasil::coords::UserLocation;
+    use skrifa::Tag;
+
+    use super::{AxisRange, extremes, grid, thin, weight_values};
+    use crate::test_util::{WDTH, WGHT, loc};
+
+    const OPSZ: Tag = Tag::new(b"opsz");
+
+    fn axes() -> Vec<AxisRange> {
+        vec![
+            AxisRange {
+                tag: WGHT,
+                min: 100.0,
+                default: 400.0,
+                max: 900.0,
+            },
+            AxisRange {
+                tag: WDTH,
+                min: 75.0,
+                default: 100.0,
+                max: 100.0,
+            },
+            AxisRange {
+                tag: OPSZ,
+                min: 8.0,
+                default: 14.0,
+                max: 144.0,
+            },
+        ]
+    }
+
+    #[test]
+    fn extremes_are_default_plus_each_axis_min_and_max() {
+        assert_eq!(
+            vec![
+                UserLocation::new(),
+                loc(&[(WGHT, 100.0)]),
+                loc(&[(WGHT, 900.0)]),
+                // wdth max is its default
+                loc(&[(WDTH, 75.0)]),
+                loc(&[(OPSZ, 8.0)]),
+                loc(&[(OPSZ, 144.0)]),
+            ],
+            extremes(&axes())
+        );
+    }
+
+    #[test]
+    fn grid_is_every_combination() {
+        let locations = grid(&axes(), 3, 64);
+        assert_eq!(27, locations.len());
+        assert!(locations.contains(&loc(&[(WGHT, 500.0), (WDTH, 87.5), (OPSZ, 76.0)])));
+        assert!(locations.contains(&loc(&[(WGHT, 100.0), (WDTH, 100.0), (OPSZ, 144.0)])));
+
+        assert_eq!(8, grid(&axes(), 2, 64).len());
+        // A single step is the default, which doesn't vary
+        assert_eq!(vec![UserLocation::new()], grid(&axes(), 1, 64));
+        assert_eq!(vec![UserLocation::new()], grid(&axes(), 0, 64));
+    }
+
+    #[test]
+    fn huge_grid_is_capped_as_it_is_built() {
+        // 5^13, over a billion combinations, enough to exhaust memory if built in full
+        let axes = (0..13)
+            .map(|i| AxisRange {
+                tag: Tag::new(&[b'a', b'x', b'0' + i / 10, b'0' + i % 10]),
+                min: 0.0,
+                default: 50.0,
+                max: 100.0,
+            })
+            .collect::<Vec<_>>();
+        let locations = grid(&axes, 5, 100);
+        assert_eq!(100, locations.len());
+
+        // Everything thinning would keep is there, so it keeps all of them
+        assert_eq!(locations, thin(locations.clone(), 100, &axes));
+        assert!(locations.iter().all(|loc| loc.iter().count() == 13));
+        let default = loc(&axes.iter().map(|a| (a.tag, 50.0)).collect::<Vec<_>>());
+        assert!(locations.contains(&default));
+        for axis in axes.iter() {
+            for value in [axis.min, axis.max] {
+                assert!(
+                    locations.iter().any(|loc| axis.coord(loc) == value),
+                    "{} {value}",
+                    axis.tag
+                );
+            }
+        }
+        // Spread evenly, not bunched at the start
+        assert!(
+            locations
+                .iter()
+                .filter(|loc| axes[0].coord(loc) == 100.0)
+                .count()
+                > 10
+        );
+    }
+
+    #[test]
+    fn thin_keeps_default_and_extremes() {
+        let axes = axes();
+        let mut locations = grid(&axes, 5, usize::MAX);
+        assert_eq!(125, locations.len());
+        // The default isn't on a 5 step grid of these axes
+        locations.insert(60, UserLocation::new());
+
+        let thinned = thin(locations.clone(), 20, &axes);
+        assert_eq!(20, thinned.len());
+        assert!(thinned.contains(&UserLocation::new()));
+        for axis in axes.iter() {
+            for value in [axis.min, axis.max] {
+                assert!(
+                    thinned.iter().any(|loc| axis.coord(loc) == value),
+                    "{} {value}",
+                    axis.tag
+                );
+            }
+        }
+        // Original order is kept
+        let positions = thinned
+            .iter()
+            .map(|t| locations.iter().position(|l| l == t).unwrap())
+            .collect::<Vec<_>>();
+        assert!(positions.is_sorted(), "{positions:?}");
+    }
+
+    #[test]
+    fn thin_below_the_limit_keeps_everything() {
+        let locations = extremes(&axes());
+        assert_eq!(locations, thin(locations.clone(), 6, &axes()));
+        // Even the locations that must be kept are thinned to fit
+        assert_eq!(2, thin(locations, 2, &axes()).len());
+    }
+
+    #[test]
+    fn weights_include_canonical_steps_and_axis_extremes() {
+        assert_eq!(
+            vec![250.0, 300.0, 400.0, 500.0, 600.0, 700.0, 800.0, 850.0],
+            weight_values(250.0, 400.0, 850.0, 100.0)
+        );
+    }
+
+    #[test]
+    fn weights_include_an_off_step_default() {
+        assert_eq!(
+            vec![100.0, 200.0, 300.0, 350.0, 400.0],
+            weight_values(100.0, 350.0, 400.0, 100.0)
+        );
+    }
+
+    #[test]
+    fn weights_without_a_step_are_just_the_extremes() {
+        assert_eq!(
+            vec![250.0, 400.0, 850.0],
+            weight_values(250.0, 400.0, 850.0, 0.0)
+        );
+    }
+}
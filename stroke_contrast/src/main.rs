@@ -1,12 +1,16 @@
 use std::{env::home_dir, fs, path::PathBuf};
 
-use args::{Args, SegmentSelection};
+use args::Args;
 use clap::Parser;
 use fontdrasil::coords::UserLocation;
-use log::info;
+use log::{info, warn};
 use read_fonts::types::NameId;
-use skrifa::{MetadataProvider, raw::TableProvider};
-use stroke_contrast::{WidthReader, csv_fragment, locations_of_interest, normalization_scale};
+use skrifa::{GlyphId, MetadataProvider, Tag, raw::TableProvider};
+use stroke_contrast::{
+    GlyphSelector, HintedStems, WidthCurve, WidthInterpolator, csv_fragment, instance_name,
+    is_default_location, locations_of_interest, measure_glyph_set, measurement_char,
+    normalization_scale, parse_glyph_set,
+};
 
 mod args;
 
@@ -52,6 +56,19 @@ fn filename_fragment(user: &UserLocation) -> String {
         .join("_")
 }
 
+fn glyph_filename_fragment(glyph: &GlyphSelector) -> String {
+    match glyph {
+        GlyphSelector::Char(ch) => format!("u{:04X}", *ch as u32),
+        GlyphSelector::Text(text) => text
+            .chars()
+            .map(|ch| format!("u{:04X}", ch as u32))
+            .collect::<Vec<_>>()
+            .join("_"),
+        GlyphSelector::Id(gid) => format!("gid{}", gid.to_u32()),
+        GlyphSelector::Name(name) => name.clone(),
+    }
+}
+
 fn main() {
     let args = Args::parse();
     setup_logging(args.log.as_deref());
@@ -67,7 +84,35 @@ fn main() {
         fs::read(&font_path).unwrap_or_else(|e| panic!("Unable to read {font_path:?}: {e}"));
     let font = skrifa::FontRef::new(&raw_font).expect("A font");
 
-    let locs = locations_of_interest(&font);
+    let mut glyphs = match &args.chars {
+        Some(chars) => parse_glyph_set(chars),
+        None if args.text.is_empty() && args.glyph.is_empty() && args.gid.is_empty() => vec![
+            measurement_char(
+                &font,
+                args.shaping
+                    .script
+                    .map(|script| script.tag().to_string())
+                    .as_deref(),
+            )
+            .expect("Font must support a measurement char, try --chars"),
+        ],
+        None => Vec::new(),
+    }
+    .into_iter()
+    .map(GlyphSelector::from)
+    .collect::<Vec<_>>();
+    glyphs.extend(args.text.iter().cloned().map(GlyphSelector::Text));
+    glyphs.extend(args.glyph.iter().cloned().map(GlyphSelector::Name));
+    glyphs.extend(
+        args.gid
+            .iter()
+            .map(|gid| GlyphSelector::Id(GlyphId::new(*gid))),
+    );
+    info!(
+        "Measuring {glyphs:?} shaped with {:?} with {:?}",
+        args.shaping, args.measure
+    );
+    let locs = locations_of_interest(&font, &args.locations);
     let scale = normalization_scale(&font);
     let name = name(&font);
 
@@ -84,53 +129,204 @@ fn main() {
     );
     debug_html.push_str("<div class=\"grid\">\n");
 
-    for user_loc in locs.iter() {
-        let norm_loc = font.axes().location(
-            &user_loc
-                .iter()
-                .map(|(tag, coord)| (tag.clone(), coord.to_f64() as f32))
-                .collect::<Vec<_>>(),
-        );
-        let builder = WidthReader::new(&raw_font, args.char, &norm_loc);
+    let hints = args.hints.then(|| HintedStems::new(&font));
+    if let Some(hints) = &hints {
+        info!("Hints {hints:?}");
+    }
 
-        let width_candidates = match args.method {
-            SegmentSelection::CenterOfMass => builder.cast_rays_around_center_of_mass(),
-            SegmentSelection::AllSegments => builder.cast_rays_from_all_segments(),
+    let measured = locs
+        .iter()
+        .map(|user_loc| {
+            let norm_loc = font.axes().location(
+                user_loc
+                    .iter()
+                    .map(|(tag, coord)| (*tag, coord.to_f64() as f32))
+                    .collect::<Vec<_>>(),
+            );
+            measure_glyph_set(
+                &raw_font,
+                &glyphs,
+                &args.shaping,
+                &norm_loc,
+                args.method,
+                args.measure,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    // Profile the same glyphs everywhere, those that fail anywhere are left out
+    let pooled = glyphs
+        .iter()
+        .filter(|glyph| measured.iter().all(|m| m.measured(glyph)))
+        .cloned()
+        .collect::<Vec<_>>();
+    if pooled.is_empty() {
+        eprintln!("No glyph could be measured at every location");
+    }
+
+    let mut measurements = Vec::new();
+    for (user_loc, measured) in locs.iter().zip(measured.iter()) {
+        // Variable font rows are labeled by named instance, if any, as well as coordinates
+        let row = if font.axes().is_empty() {
+            format!("{name}, {}", csv_fragment(user_loc))
+        } else {
+            format!(
+                "{name}, {}, {}",
+                instance_name(&font, user_loc).unwrap_or_default(),
+                csv_fragment(user_loc)
+            )
         };
+        for (glyph, measurement) in measured.glyphs.iter() {
+            match measurement {
+                Ok(measurement) => {
+                    info!(
+                        "{glyph} width {:.2}..{:.2}, {} contrast",
+                        measurement.candidates.min_width * scale,
+                        measurement.candidates.max_width * scale,
+                        measurement.candidates.contrast.class
+                    );
+                    if !measurement.reader.contours.is_clean() {
+                        warn!("{row}, {glyph}, {}", measurement.reader.contours);
+                    }
+                }
+                Err(e) => eprintln!("{row}, {glyph}, {e}"),
+            }
+        }
+        let Some(width_candidates) = measured.profile_of(&pooled) else {
+            continue;
+        };
+        measurements.push((
+            user_loc.clone(),
+            width_candidates.min_width * scale,
+            width_candidates.max_width * scale,
+        ));
 
         // Emit tags in normalized scale
         println!(
-            "{name}, {}, /quant/stroke_width_min, {:.2}",
-            csv_fragment(user_loc),
+            "{row}, /quant/stroke_width_min, {:.2}",
             width_candidates.min_width * scale
         );
         println!(
-            "{name}, {}, /quant/stroke_width_max, {:.2}",
-            csv_fragment(user_loc),
+            "{row}, /quant/stroke_width_max, {:.2}",
             width_candidates.max_width * scale
         );
+        println!(
+            "{row}, /quant/stroke_contrast, {:.2}",
+            width_candidates.contrast.ratio
+        );
+        info!("{} contrast", width_candidates.contrast.class);
+        if let Some(stress) = width_candidates.stress() {
+            info!("Stress confidence {:.2}", stress.confidence);
+            if stress.confidence >= args.min_stress_confidence {
+                println!("{row}, /quant/stress_angle, {:.1}", stress.angle);
+            }
+        }
+        if args.percentiles {
+            println!(
+                "{row}, /quant/stroke_width_p5, {:.2}",
+                width_candidates.distribution.p5 * scale
+            );
+            println!(
+                "{row}, /quant/stroke_width_p95, {:.2}",
+                width_candidates.distribution.p95 * scale
+            );
+        }
+        if let Some(vertical_stem) = &width_candidates.stems.vertical_stem {
+            println!(
+                "{row}, /quant/vertical_stem_width, {:.2}",
+                vertical_stem.median * scale
+            );
+        }
+        if let Some(horizontal_bar) = &width_candidates.stems.horizontal_bar {
+            println!(
+                "{row}, /quant/horizontal_bar_width, {:.2}",
+                horizontal_bar.median * scale
+            );
+        }
+        if let Some(hints) = &hints
+            && is_default_location(&font, user_loc)
+        {
+            // Diagnostics, not tags, so they stay out of stdout
+            for disagreement in hints.compare(&width_candidates.stems) {
+                eprintln!(
+                    "{row}, {} {:.2} disagrees with measured {:.2}",
+                    disagreement.source,
+                    disagreement.hinted * scale,
+                    disagreement.measured * scale
+                );
+            }
+            for nearest in hints.nearest_cvt(&width_candidates.stems) {
+                eprintln!(
+                    "{row}, cvt {:.2} is nearest measured {} {:.2}",
+                    nearest.cvt * scale,
+                    nearest.stem,
+                    nearest.measured * scale
+                );
+            }
+        }
+        if let Some(diagonal) = &width_candidates.stems.diagonal {
+            info!("Diagonal stroke width {:.2}", diagonal.median * scale);
+        }
+        info!(
+            "Width histogram {:?}",
+            width_candidates.distribution.histogram(20)
+        );
 
-        let svg = builder.debug_svg(args.show_rays, &width_candidates);
+        for (glyph, measurement) in measured.glyphs.iter() {
+            let Ok(measurement) = measurement else {
+                continue;
+            };
+            let svg = measurement
+                .reader
+                .debug_svg(args.show_rays, &measurement.candidates);
 
-        let output_file = PathBuf::from(&args.output_svg);
-        let output_file = output_file.with_file_name(format!(
-            "{}{}.{}",
-            output_file.file_stem().unwrap().to_str().unwrap(),
-            filename_fragment(user_loc),
-            output_file.extension().unwrap().to_str().unwrap()
-        ));
-        info!("Writing {:?}", output_file);
-        fs::write(&output_file, &svg).expect("To write output file");
-
-        // debug_html.push_str("<div>\n");
-        // debug_html.push_str(output_file.file_stem().unwrap().to_str().unwrap());
-        // debug_html.push_str("</div><div>\n");
-        debug_html.push_str("<div>\n");
-        debug_html.push_str(&svg);
-        debug_html.push_str("</div>\n");
+            // Only distinguish files by glyph when there is more than one
+            let glyph_fragment = if glyphs.len() > 1 {
+                format!("_{}", glyph_filename_fragment(glyph))
+            } else {
+                String::new()
+            };
+            let output_file = PathBuf::from(&args.output_svg);
+            let output_file = output_file.with_file_name(format!(
+                "{}{}{glyph_fragment}.{}",
+                output_file.file_stem().unwrap().to_str().unwrap(),
+                filename_fragment(user_loc),
+                output_file.extension().unwrap().to_str().unwrap()
+            ));
+            info!("Writing {:?}", output_file);
+            fs::write(&output_file, &svg).expect("To write output file");
+
+            debug_html.push_str("<div>\n");
+            debug_html.push_str(&svg);
+            debug_html.push_str("</div>\n");
+        }
     }
     debug_html.push_str("</div>\n");
 
+    if !args.estimate.is_empty() {
+        let interpolator = WidthInterpolator::new(&font, &measurements);
+        for loc in args.estimate.iter() {
+            match interpolator.estimate(loc) {
+                Some(estimate) => println!(
+                    "Estimated at {}: width {:.2}..{:.2}",
+                    csv_fragment(loc),
+                    estimate.min_width,
+                    estimate.max_width
+                ),
+                None => eprintln!(
+                    "Unable to estimate at {}, is every axis in the font?",
+                    csv_fragment(loc)
+                ),
+            }
+        }
+    }
+
+    if args.curve {
+        for curve in WidthCurve::along(Tag::new(b"wght"), &measurements) {
+            println!("{curve}");
+        }
+    }
+
     if let Some(debug_html_file) = &args.debug_html {
         let debug_html_file = PathBuf::from(&debug_html_file);
         info!("Writing {:?}", debug_html_file);
@@ -0,0 +1,119 @@
+use std::time::Duration;
+
+use crate::FillRule;
+
+/// How to fit a circle into the ink around a rib
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+pub enum CircleFit {
+    /// The largest circle centered on the midpoint of the rib
+    #[default]
+    Centered,
+    /// The largest circle touching the wall nearest the midpoint of the rib, which also
+    /// touches the opposite wall
+    Maximal,
+}
+
+/// Tuning knobs for stroke width measurement, trading precision for speed.
+///
+/// Derives [clap::Args] so binaries can flatten it into their own arguments.
+#[derive(Debug, Clone, Copy, PartialEq, clap::Args)]
+#[command(about = None, long_about = None)]
+pub struct MeasureOptions {
+    /// How many rays to spray, evenly spaced around a full circle, from the center of mass
+    #[arg(long, default_value_t = MeasureOptions::default().ray_count)]
+    pub ray_count: usize,
+
+    /// How many points along each path segment to cast rays from or fit circles to
+    #[arg(long, default_value_t = MeasureOptions::default().samples_per_segment)]
+    pub samples_per_segment: usize,
+
+    /// How to fit a circle to each rib found by casting rays
+    #[arg(long, value_enum, default_value_t = MeasureOptions::default().circle_fit)]
+    pub circle_fit: CircleFit,
+
+    /// Discard ribs whose centered circle spans less than this fraction of them, such ribs cut
+    /// across a corner or a terminal rather than a stroke
+    #[arg(long, default_value_t = MeasureOptions::default().min_rib_coverage)]
+    pub min_rib_coverage: f64,
+
+    /// Circles of this radius or less are discarded as noise, in font units
+    #[arg(long, default_value_t = MeasureOptions::default().min_radius)]
+    pub min_radius: f64,
+
+    /// Inflate the glyph bounding box by this fraction of its largest dimension
+    #[arg(long, default_value_t = MeasureOptions::default().bbox_margin)]
+    pub bbox_margin: f64,
+
+    /// How winding numbers map to ink
+    #[arg(long, value_enum, default_value_t = MeasureOptions::default().fill_rule)]
+    pub fill_rule: FillRule,
+
+    /// Treat the glyph as reverse video, a hole cut out of a box, if more than this fraction of
+    /// the bounding box is inked. The box is dropped if a single box shaped contour frames the
+    /// rest.
+    #[arg(long, default_value_t = MeasureOptions::default().reverse_video_threshold)]
+    pub reverse_video_threshold: f64,
+
+    /// Medial axis circles must touch the outline at points at least this many degrees apart
+    #[arg(long, default_value_t = MeasureOptions::default().min_object_angle)]
+    pub min_object_angle: f64,
+
+    /// Give up if a measurement would cast more than this many rays
+    #[arg(long)]
+    pub max_rays: Option<usize>,
+
+    /// Give up, keeping what was found so far, after this many segment intersection, winding or
+    /// nearest point tests, preparing the outline included
+    #[arg(long)]
+    pub max_intersection_tests: Option<usize>,
+
+    /// Give up, keeping what was found so far, after this many seconds, preparing the outline
+    /// included
+    #[arg(long = "timeout-secs", value_parser = parse_timeout)]
+    pub timeout: Option<Duration>,
+}
+
+impl Default for MeasureOptions {
+    fn default() -> Self {
+        Self {
+            ray_count: 360,
+            samples_per_segment: 10,
+            circle_fit: CircleFit::Centered,
+            min_rib_coverage: 0.875,
+            min_radius: 1.0,
+            bbox_margin: 0.03,
+            fill_rule: FillRule::NonZero,
+            reverse_video_threshold: 0.75,
+            min_object_angle: 135.0,
+            max_rays: None,
+            max_intersection_tests: None,
+            timeout: None,
+        }
+    }
+}
+
+/// Parse a number of seconds, rejecting negative, NaN and unrepresentably large values
+fn parse_timeout(s: &str) -> Result<Duration, String> {
+    let secs = s.trim().parse::<f64>().map_err(|e| format!("{s:?}: {e}"))?;
+    Duration::try_from_secs_f64(secs).map_err(|e| format!("{s:?}: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::parse_timeout;
+
+    #[test]
+    fn parse_valid_timeout() {
+        assert_eq!(Ok(Duration::from_millis(2500)), parse_timeout("2.5"));
+        assert_eq!(Ok(Duration::ZERO), parse_timeout("0"));
+    }
+
+    #[test]
+    fn reject_invalid_timeout() {
+        for s in ["-1", "NaN", "inf", "1e300", "soon"] {
+            assert!(parse_timeout(s).is_err(), "{s}");
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use kurbo::{Circle, Line};
+
+use crate::WidthDistribution;
+
+/// Degrees either side of horizontal or vertical a rib may be and still count as such
+const ORIENTATION_TOLERANCE: f64 = 30.0;
+
+/// The orientation of a rib in degrees, [0, 180)
+pub(crate) fn rib_angle(rib: &Line) -> f64 {
+    (rib.p1 - rib.p0).angle().to_degrees().rem_euclid(180.0)
+}
+
+/// Which way a rib runs. A rib crosses the stroke it measures so a near-horizontal rib measures
+/// a vertical stem and a near-vertical rib a horizontal bar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RibOrientation {
+    NearHorizontal,
+    NearVertical,
+    Diagonal,
+}
+
+impl RibOrientation {
+    pub fn of(rib: &Line) -> Self {
+        let angle = rib_angle(rib);
+        if (angle - 90.0).abs() < ORIENTATION_TOLERANCE {
+            RibOrientation::NearVertical
+        } else if !(ORIENTATION_TOLERANCE..=180.0 - ORIENTATION_TOLERANCE).contains(&angle) {
+            RibOrientation::NearHorizontal
+        } else {
+            RibOrientation::Diagonal
+        }
+    }
+}
+
+/// Widths by stroke orientation, like the stem and bar thicknesses type designers talk about.
+///
+/// None where no rib had that orientation.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StemWidths {
+    /// Measured by near-horizontal ribs
+    pub vertical_stem: Option<WidthDistribution>,
+    /// Measured by near-vertical ribs
+    pub horizontal_bar: Option<WidthDistribution>,
+    pub diagonal: Option<WidthDistribution>,
+}
+
+impl StemWidths {
+    pub fn new(ribs: &[(Line, Circle)]) -> Self {
+        let distribution = |orientation| {
+            let widths = ribs
+                .iter()
+                .filter(|(rib, _)| RibOrientation::of(rib) == orientation)
+                .map(|(_, circle)| 2.0 * circle.radius)
+                .collect::<Vec<_>>();
+            (!widths.is_empty()).then(|| WidthDistribution::new(widths))
+        };
+        Self {
+            vertical_stem: distribution(RibOrientation::NearHorizontal),
+            horizontal_bar: distribution(RibOrientation::NearVertical),
+            diagonal: distribution(RibOrientation::Diagonal),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::{Circle, Line, Point, Vec2};
+
+    use super::{RibOrientation, StemWidths};
+
+    fn rib_at(degrees: f64) -> Line {
+        Line::new(
+            Point::ORIGIN,
+            Point::ORIGIN + 100.0 * Vec2::from_angle(degrees.to_radians()),
+        )
+    }
+
+    #[test]
+    fn orientation_buckets() {
+        for (degrees, orientation) in [
+            (0.0, RibOrientation::NearHorizontal),
+            (29.5, RibOrientation::NearHorizontal),
+            (30.5, RibOrientation::Diagonal),
+            (45.0, RibOrientation::Diagonal),
+            (59.5, RibOrientation::Diagonal),
+            (60.5, RibOrientation::NearVertical),
+            (90.0, RibOrientation::NearVertical),
+            (119.5, RibOrientation::NearVertical),
+            (120.5, RibOrientation::Diagonal),
+            (149.5, RibOrientation::Diagonal),
+            (150.5, RibOrientation::NearHorizontal),
+            (180.0, RibOrientation::NearHorizontal),
+        ] {
+            let rib = rib_at(degrees);
+            assert_eq!(orientation, RibOrientation::of(&rib), "{degrees}");
+            // Which end a rib starts from doesn't matter
+            let reversed = Line::new(rib.p1, rib.p0);
+            assert_eq!(orientation, RibOrientation::of(&reversed), "-{degrees}");
+        }
+    }
+
+    #[test]
+    fn stems_by_orientation() {
+        let ribs = [(0.0, 80.0), (10.0, 90.0), (90.0, 20.0), (45.0, 50.0)]
+            .map(|(degrees, width)| (rib_at(degrees), Circle::new(Point::ORIGIN, width / 2.0)));
+        let stems = StemWidths::new(&ribs);
+        assert_eq!(Some(85.0), stems.vertical_stem.map(|d| d.median));
+        assert_eq!(Some(20.0), stems.horizontal_bar.map(|d| d.median));
+        assert_eq!(Some(50.0), stems.diagonal.map(|d| d.median));
+    }
+
+    #[test]
+    fn empty_buckets_are_none() {
+        let ribs = [(rib_at(0.0), Circle::new(Point::ORIGIN, 40.0))];
+        let stems = StemWidths::new(&ribs);
+        assert!(stems.vertical_stem.is_some());
+        assert_eq!(None, stems.horizontal_bar);
+        assert_eq!(None, stems.diagonal);
+
+        assert_eq!(StemWidths::default(), StemWidths::new(&[]));
+    }
+}
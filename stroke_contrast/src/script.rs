@@ -0,0 +1,111 @@
+use skrifa::{FontRef, MetadataProvider};
+
+/// Representative measurement glyphs by ISO 15924 script code, in order of preference.
+///
+/// Where possible we want the script's analogue of 'o': a closed round with an unambiguous
+/// counter, so the center of mass is uninked and rays see thick and thin strokes.
+/// Latin comes first so fonts with no script information behave as they always did.
+pub const MEASUREMENT_CHARS: &[(&str, &[char])] = &[
+    ("Latn", &['o']),
+    ("Cyrl", &['о']),
+    ("Grek", &['ο']),
+    ("Armn", &['օ']),
+    ("Geor", &['ო']),
+    ("Hebr", &['ס']),
+    ("Arab", &['ه']),
+    ("Deva", &['०', 'ठ']),
+    ("Beng", &['০', 'ঠ']),
+    ("Guru", &['੦', 'ਠ']),
+    ("Gujr", &['૦', 'ઠ']),
+    ("Orya", &['୦', 'ଠ']),
+    ("Taml", &['௦']),
+    ("Telu", &['౦', 'ఠ']),
+    ("Knda", &['೦', 'ಠ']),
+    ("Mlym", &['൦', 'ഠ']),
+    ("Sinh", &['ට']),
+    ("Thai", &['๐', 'อ']),
+    ("Laoo", &['໐', 'ອ']),
+    ("Mymr", &['၀', 'ဝ']),
+    ("Khmr", &['០']),
+    ("Tibt", &['༠']),
+    ("Ethi", &['ዐ']),
+    ("Hani", &['口']),
+    ("Hans", &['口']),
+    ("Hant", &['口']),
+    ("Jpan", &['口', 'ロ']),
+    ("Kore", &['ㅇ', '口']),
+];
+
+/// The representative measurement glyphs for an ISO 15924 script code, empty if we don't know any
+pub fn script_measurement_chars(script: &str) -> &'static [char] {
+    MEASUREMENT_CHARS
+        .iter()
+        .find(|(code, _)| *code == script)
+        .map(|(_, chars)| *chars)
+        .unwrap_or_default()
+}
+
+/// Pick a measurement glyph the font actually maps.
+///
+/// Prefers glyphs for primary_script, typically from the family metadata, then falls back to
+/// the first script in [MEASUREMENT_CHARS] the font supports. None if the font supports none.
+pub fn measurement_char(font: &FontRef, primary_script: Option<&str>) -> Option<char> {
+    let charmap = font.charmap();
+    first_mapped(primary_script, |ch| charmap.map(ch).is_some())
+}
+
+/// The first measurement glyph, in order of preference, that is_mapped accepts
+fn first_mapped(primary_script: Option<&str>, is_mapped: impl Fn(char) -> bool) -> Option<char> {
+    primary_script
+        .map(script_measurement_chars)
+        .into_iter()
+        .chain(MEASUREMENT_CHARS.iter().map(|(_, chars)| *chars))
+        .flatten()
+        .copied()
+        .find(|ch| is_mapped(*ch))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MEASUREMENT_CHARS, first_mapped, script_measurement_chars};
+
+    /// The measurement glyph picked for a font that maps only charset
+    fn pick(primary_script: Option<&str>, charset: &str) -> Option<char> {
+        first_mapped(primary_script, |ch| charset.contains(ch))
+    }
+
+    #[test]
+    fn latin_comes_first() {
+        assert_eq!("Latn", MEASUREMENT_CHARS[0].0);
+    }
+
+    #[test]
+    fn primary_script_is_preferred() {
+        assert_eq!(Some('ο'), pick(Some("Grek"), "oο"));
+        assert_eq!(Some('ठ'), pick(Some("Deva"), "oठ"));
+        // In the order listed for the script
+        assert_eq!(Some('०'), pick(Some("Deva"), "oठ०"));
+    }
+
+    #[test]
+    fn falls_back_to_latin() {
+        // The font doesn't map what the primary script would use
+        assert_eq!(Some('o'), pick(Some("Grek"), "oо"));
+        assert_eq!(Some('o'), pick(Some("Zzzz"), "oο"));
+        assert_eq!(Some('o'), pick(None, "оoο"));
+    }
+
+    #[test]
+    fn falls_back_in_table_order() {
+        assert_eq!(Some('о'), pick(None, "οо"));
+        assert_eq!(Some('о'), pick(Some("Hebr"), "ο口о"));
+        assert_eq!(Some('口'), pick(Some("Kore"), "口"));
+        assert_eq!(None, pick(Some("Latn"), "xyz"));
+    }
+
+    #[test]
+    fn unknown_script_has_no_chars() {
+        assert!(script_measurement_chars("Zzzz").is_empty());
+        assert_eq!(&['口', 'ロ'], script_measurement_chars("Jpan"));
+    }
+}
@@ -0,0 +1,222 @@
+//! Where to cast rays from when the center of mass is inked.
+//!
+//! Rays sprayed from inside a counter cross the strokes around it, which is what the center of
+//! mass gives us for an 'o'. For an 'e', an 'a' or a heavy weight the center of mass lands in ink
+//! so we look for points well inside each counter instead.
+
+use std::collections::BinaryHeap;
+
+use kurbo::{Point, Rect};
+use ordered_float::OrderedFloat;
+
+/// Give up refining the pole after this many cells, it only needs to be well inside the counter
+const MAX_CELLS: usize = 4096;
+
+/// The most cells the initial grid may take, leaving the rest of [MAX_CELLS] to refine it
+const MAX_INITIAL_CELLS: usize = MAX_CELLS / 4;
+
+/// A square cell of the search grid
+#[derive(Clone, Copy)]
+struct Cell {
+    center: Point,
+    half: f64,
+    /// Signed distance from center to the region edge, positive inside
+    distance: f64,
+}
+
+impl Cell {
+    fn new<E>(
+        center: Point,
+        half: f64,
+        signed_distance: &impl Fn(Point) -> Result<f64, E>,
+    ) -> Result<Self, E> {
+        Ok(Self {
+            center,
+            half,
+            distance: signed_distance(center)?,
+        })
+    }
+
+    /// The furthest any point in the cell could be from the region edge
+    fn potential(&self) -> f64 {
+        self.distance + self.half * std::f64::consts::SQRT_2
+    }
+}
+
+/// The pole of inaccessibility of a region, the point inside it furthest from its edge, and
+/// that distance.
+///
+/// Follows polylabel: subdivide the bounding box into cells, always refining the cell that could
+/// hold the furthest point, until no cell could beat the best found by more than precision.
+/// signed_distance is positive inside the region, an error from it, e.g. running out of budget,
+/// stops the search. None if nothing inside was found.
+pub(crate) fn pole_of_inaccessibility<E>(
+    bbox: Rect,
+    precision: f64,
+    signed_distance: impl Fn(Point) -> Result<f64, E>,
+) -> Result<Option<(Point, f64)>, E> {
+    let mut size = bbox.width().min(bbox.height());
+    if size <= 0.0 {
+        return Ok(None);
+    }
+    // Cells as big as the short side of a long thin box would be too many, use bigger ones
+    let cell_count = |size: f64| (bbox.width() / size).ceil() * (bbox.height() / size).ceil();
+    while cell_count(size) > MAX_INITIAL_CELLS as f64 {
+        size *= 2.0;
+    }
+    let half = size / 2.0;
+
+    let mut queue = BinaryHeap::new();
+    let mut cells = Vec::new();
+    let mut x = bbox.min_x();
+    while x < bbox.max_x() {
+        let mut y = bbox.min_y();
+        while y < bbox.max_y() {
+            let cell = Cell::new(Point::new(x + half, y + half), half, &signed_distance)?;
+            push(&mut queue, &mut cells, cell);
+            y += size;
+        }
+        x += size;
+    }
+
+    let mut best = (bbox.center(), signed_distance(bbox.center())?);
+    while let Some((OrderedFloat(potential), i)) = queue.pop() {
+        let Cell {
+            center,
+            half,
+            distance,
+        } = cells[i];
+        if distance > best.1 {
+            best = (center, distance);
+        }
+        // The queue is ordered by potential so nothing left can do better
+        if potential - best.1 <= precision || cells.len() >= MAX_CELLS {
+            break;
+        }
+        let half = half / 2.0;
+        for (dx, dy) in [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)] {
+            let cell = Cell::new(center + (dx * half, dy * half), half, &signed_distance)?;
+            push(&mut queue, &mut cells, cell);
+        }
+    }
+    Ok((best.1 > 0.0).then_some(best))
+}
+
+/// Queue cell, most promising first
+fn push(queue: &mut BinaryHeap<(OrderedFloat<f64>, usize)>, cells: &mut Vec<Cell>, cell: Cell) {
+    queue.push((OrderedFloat(cell.potential()), cells.len()));
+    cells.push(cell);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, convert::Infallible};
+
+    use kurbo::{Point, Rect};
+
+    use super::{MAX_CELLS, pole_of_inaccessibility};
+
+    /// Distance to the nearest edge of rect, positive inside
+    fn rect_distance(rect: Rect) -> impl Fn(Point) -> Result<f64, Infallible> {
+        move |pt| {
+            let inside = (pt.x - rect.min_x())
+                .min(rect.max_x() - pt.x)
+                .min(pt.y - rect.min_y())
+                .min(rect.max_y() - pt.y);
+            Ok(inside)
+        }
+    }
+
+    #[test]
+    fn pole_of_square_is_its_center() {
+        let square = Rect::new(100.0, 100.0, 300.0, 300.0);
+        let (pole, distance) = pole_of_inaccessibility(square, 0.5, rect_distance(square))
+            .unwrap()
+            .unwrap();
+        assert!((pole - square.center()).length() <= 0.5, "{pole:?}");
+        assert!((distance - 100.0).abs() <= 0.5, "{distance}");
+    }
+
+    #[test]
+    fn pole_of_rectangle_is_on_its_center_line() {
+        let rect = Rect::new(0.0, 0.0, 400.0, 100.0);
+        let (pole, distance) = pole_of_inaccessibility(rect, 0.5, rect_distance(rect))
+            .unwrap()
+            .unwrap();
+        assert!((pole.y - 50.0).abs() <= 0.5, "{pole:?}");
+        assert!((distance - 50.0).abs() <= 0.5, "{distance}");
+    }
+
+    #[test]
+    fn cell_limit_returns_best_so_far() {
+        // A ring, the search keeps finding slightly better points and with no precision to
+        // settle for never stops on its own
+        let evaluations = Cell::new(0);
+        let ring_distance = |pt: Point| {
+            evaluations.set(evaluations.get() + 1);
+            let r = (pt - Point::new(500.0, 500.0)).length();
+            Ok::<_, Infallible>((r - 200.0).min(400.0 - r))
+        };
+        let (pole, distance) =
+            pole_of_inaccessibility(Rect::new(0.0, 0.0, 1000.0, 1000.0), 0.0, ring_distance)
+                .unwrap()
+                .unwrap();
+        // Stopped by the limit, not by finding the pole
+        assert!(
+            (MAX_CELLS..=MAX_CELLS + 5).contains(&evaluations.get()),
+            "{}",
+            evaluations.get()
+        );
+        assert!(distance > 90.0, "{pole:?} {distance}");
+    }
+
+    #[test]
+    fn initial_grid_of_thin_box_is_capped() {
+        let evaluations = Cell::new(0);
+        let thin = Rect::new(0.0, 0.0, 1_000_000.0, 1.0);
+        let distance = |pt: Point| {
+            evaluations.set(evaluations.get() + 1);
+            rect_distance(thin)(pt)
+        };
+        let (pole, _) = pole_of_inaccessibility(thin, 0.1, distance)
+            .unwrap()
+            .unwrap();
+        assert!(
+            evaluations.get() <= MAX_CELLS + 5,
+            "{} > {MAX_CELLS}",
+            evaluations.get()
+        );
+        assert!((0.0..=1.0).contains(&pole.y), "{pole:?}");
+    }
+
+    #[test]
+    fn nothing_inside() {
+        let outside = |_: Point| Ok::<_, Infallible>(-1.0);
+        assert_eq!(
+            Ok(None),
+            pole_of_inaccessibility(Rect::new(0.0, 0.0, 10.0, 10.0), 0.5, outside)
+        );
+        assert_eq!(
+            Ok(None),
+            pole_of_inaccessibility(Rect::new(0.0, 0.0, 10.0, 0.0), 0.5, outside)
+        );
+    }
+
+    #[test]
+    fn errors_stop_the_search() {
+        let evaluations = Cell::new(0);
+        let failing = |_: Point| {
+            evaluations.set(evaluations.get() + 1);
+            if evaluations.get() > 10 {
+                Err("out of budget")
+            } else {
+                Ok(1.0)
+            }
+        };
+        assert_eq!(
+            Err("out of budget"),
+            pole_of_inaccessibility(Rect::new(0.0, 0.0, 10.0, 10.0), 0.0, failing)
+        );
+        assert_eq!(11, evaluations.get());
+    }
+}
@@ -0,0 +1,96 @@
+use harfruzz::{Feature, Language, Script, UnicodeBuffer};
+
+/// How to shape the text to measure, letting us reach alternates and localized forms
+#[derive(Debug, Clone, Default, PartialEq, clap::Args)]
+#[command(about = None, long_about = None)]
+pub struct ShapingOptions {
+    /// OpenType feature to apply when shaping, e.g. ss01, smcp, -liga or aalt=2. May be repeated.
+    #[arg(long = "feature", allow_hyphen_values = true)]
+    pub features: Vec<Feature>,
+
+    /// ISO 15924 script to shape as, e.g. Deva. Guessed from the text if unset.
+    #[arg(long)]
+    pub script: Option<Script>,
+
+    /// BCP 47 language to shape as, e.g. sr for Serbian forms of Cyrillic
+    #[arg(long)]
+    pub language: Option<Language>,
+}
+
+impl ShapingOptions {
+    /// A buffer holding text, set to shape as our script and language if given
+    pub(crate) fn buffer(&self, text: &str) -> UnicodeBuffer {
+        let mut buffer = UnicodeBuffer::new();
+        buffer.push_str(text);
+        if let Some(script) = self.script {
+            buffer.set_script(script);
+        }
+        if let Some(language) = &self.language {
+            buffer.set_language(language.clone());
+        }
+        buffer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use skrifa::Tag;
+
+    use super::ShapingOptions;
+
+    #[derive(Parser)]
+    struct Args {
+        #[command(flatten)]
+        shaping: ShapingOptions,
+    }
+
+    fn parse(args: &[&str]) -> ShapingOptions {
+        Args::try_parse_from(["test"].iter().chain(args))
+            .unwrap()
+            .shaping
+    }
+
+    #[test]
+    fn features_may_be_repeated() {
+        let shaping = parse(&[
+            "--feature",
+            "ss01",
+            "--feature",
+            "-liga",
+            "--feature",
+            "aalt=2",
+        ]);
+        assert_eq!(
+            vec![
+                (Tag::new(b"ss01"), 1),
+                (Tag::new(b"liga"), 0),
+                (Tag::new(b"aalt"), 2)
+            ],
+            shaping
+                .features
+                .iter()
+                .map(|f| (f.tag, f.value))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn script_and_language_reach_the_buffer() {
+        let buffer = parse(&["--script", "Deva", "--language", "sr"]).buffer("o");
+        assert_eq!(Tag::new(b"Deva"), buffer.script().tag());
+        assert_eq!(Some("sr"), buffer.language().as_ref().map(|l| l.as_str()));
+    }
+
+    #[test]
+    fn nothing_set_by_default() {
+        let shaping = parse(&[]);
+        assert_eq!(ShapingOptions::default(), shaping);
+        assert_eq!(None, shaping.buffer("o").language());
+    }
+
+    #[test]
+    fn bad_script_is_rejected() {
+        assert!(Args::try_parse_from(["test", "--script", "Devanagari"]).is_err());
+    }
+}
@@ -0,0 +1,114 @@
+use kurbo::{Circle, Line};
+
+use crate::orientation::rib_angle;
+
+/// Below this confidence a stress angle is more noise than axis, binaries don't report it
+pub const DEFAULT_MIN_STRESS_CONFIDENCE: f64 = 0.5;
+
+/// The axis of stress, the line through the thinnest parts of a round such as 'o'
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stress {
+    /// Degrees from vertical, in font (y-up) space, in (-90, 90].
+    ///
+    /// Positive is counter-clockwise, the top of the axis leaning left as is typical of
+    /// humanist designs. 0 is vertical, typical of Didone and many grotesques.
+    pub angle: f64,
+    /// 0..=1, how consistently the thin and thick ribs agree on the axis
+    pub confidence: f64,
+}
+
+/// Fit the axis of stress from ribs and the circles fit to them.
+///
+/// Thin ribs lie along the axis and thick ribs across it. Rib orientations are axial
+/// (a rib at 10° is the same as one at 190°) so we average doubled angles, weighting each
+/// rib by how thin (toward the axis) or thick (away from it) it is. None if there is no
+/// variation in width to go on.
+pub fn stress_angle(ribs: &[(Line, Circle)]) -> Option<Stress> {
+    let (min, max) = ribs
+        .iter()
+        .map(|(_, circle)| circle.radius)
+        .fold((f64::MAX, f64::MIN), |acc, r| (acc.0.min(r), acc.1.max(r)));
+    let half_range = (max - min) / 2.0;
+    if ribs.len() < 2 || half_range <= f64::EPSILON * max.abs() {
+        return None;
+    }
+    let mid = min + half_range;
+
+    let (mut x, mut y, mut total_weight) = (0.0, 0.0, 0.0);
+    for (rib, circle) in ribs {
+        // +1 for the thinnest, -1 for the thickest; a thick rib votes for the perpendicular axis
+        let weight = (mid - circle.radius) / half_range;
+        // Ribs live in y-down space, flip back to font space
+        let doubled = (-2.0 * rib_angle(rib)).to_radians();
+        x += weight * doubled.cos();
+        y += weight * doubled.sin();
+        total_weight += weight.abs();
+    }
+    if total_weight == 0.0 {
+        return None;
+    }
+
+    let axis = y.atan2(x).to_degrees() / 2.0;
+    let mut angle = axis - 90.0;
+    if angle <= -90.0 {
+        angle += 180.0;
+    }
+    Some(Stress {
+        angle,
+        confidence: (x.hypot(y) / total_weight).clamp(0.0, 1.0),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::{Circle, Line, Point, Vec2};
+
+    use super::stress_angle;
+
+    /// A rib through the origin along direction, given in font (y-up) space, as ribs are
+    /// found in y-down space
+    fn rib(direction: Vec2, radius: f64) -> (Line, Circle) {
+        let direction = Vec2::new(direction.x, -direction.y) * radius;
+        (
+            Line::new(Point::ORIGIN - direction, Point::ORIGIN + direction),
+            Circle::new(Point::ORIGIN, radius),
+        )
+    }
+
+    /// Thin ribs along an axis leaning degrees counter-clockwise from vertical, thick across it
+    fn ribs(degrees: f64) -> Vec<(Line, Circle)> {
+        let along = Vec2::from_angle((90.0 + degrees).to_radians());
+        let across = along.turn_90();
+        vec![
+            rib(along, 10.0),
+            rib(-along, 10.0),
+            rib(across, 50.0),
+            rib(-across, 50.0),
+        ]
+    }
+
+    #[test]
+    fn vertical_stress() {
+        let stress = stress_angle(&ribs(0.0)).unwrap();
+        assert!(stress.angle.abs() < 1e-6, "{stress:?}");
+        assert!((stress.confidence - 1.0).abs() < 1e-6, "{stress:?}");
+    }
+
+    #[test]
+    fn stress_leaning_left_is_positive() {
+        // Humanist, the top of the axis leans left
+        let stress = stress_angle(&ribs(20.0)).unwrap();
+        assert!((stress.angle - 20.0).abs() < 1e-6, "{stress:?}");
+        let stress = stress_angle(&ribs(-20.0)).unwrap();
+        assert!((stress.angle + 20.0).abs() < 1e-6, "{stress:?}");
+    }
+
+    #[test]
+    fn no_stress_without_contrast() {
+        let uniform = [
+            rib(Vec2::new(1.0, 0.0), 10.0),
+            rib(Vec2::new(0.0, 1.0), 10.0),
+        ];
+        assert_eq!(None, stress_angle(&uniform));
+    }
+}
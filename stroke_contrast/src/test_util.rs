@@ -0,0 +1,51 @@
+//! Helpers shared by the unit tests and the segment_index bench, which includes this file.
+
+use std::f64::consts::TAU;
+
+use fontdrasil::coords::{UserCoord, UserLocation};
+use kurbo::{BezPath, Point};
+use skrifa::Tag;
+
+pub(crate) const WGHT: Tag = Tag::new(b"wght");
+pub(crate) const WDTH: Tag = Tag::new(b"wdth");
+
+pub(crate) fn loc(coords: &[(Tag, f64)]) -> UserLocation {
+    let mut loc = UserLocation::new();
+    for (tag, value) in coords {
+        loc.insert(*tag, UserCoord::new(*value));
+    }
+    loc
+}
+
+/// An 'o' whose outer and inner contours are wobbly rings of quadratic segments
+pub(crate) fn wobbly_o(segments_per_contour: usize) -> BezPath {
+    let mut path = BezPath::new();
+    for (radius, clockwise) in [(500.0, false), (400.0, true)] {
+        let pt = |i: usize| {
+            let angle = TAU * i as f64 / segments_per_contour as f64;
+            let angle = if clockwise { -angle } else { angle };
+            let wobble = 1.0 + 0.01 * (37.0 * angle).sin();
+            Point::new(radius * wobble * angle.cos(), radius * wobble * angle.sin())
+        };
+        path.move_to(pt(0));
+        for i in 0..segments_per_contour {
+            let mid = pt(i).midpoint(pt(i + 1));
+            let ctrl = mid + 0.02 * (mid - Point::ORIGIN);
+            path.quad_to(ctrl, pt(i + 1));
+        }
+        path.close_path();
+    }
+    path
+}
+
+/// Points spread over the bounding box of [wobbly_o], none level with a vertex
+pub(crate) fn query_points(count: usize) -> Vec<Point> {
+    (0..count)
+        .map(|i| {
+            // Golden angle spiral, deterministic and well spread
+            let r = 550.0 * (i as f64 / count as f64).sqrt();
+            let angle = i as f64 * 2.399963;
+            Point::new(r * angle.cos(), r * angle.sin() + 0.123)
+        })
+        .collect()
+}